@@ -0,0 +1,63 @@
+#![feature(test)]
+
+extern crate test;
+
+use json::{
+    input_reader::MemoryReader,
+    lexer::{Lexer, LexerOptions},
+};
+use test::Bencher;
+
+fn indented_source() -> String {
+    let mut source = String::from("[\n");
+    for i in 0..1024 {
+        source.push_str(&format!(
+            "        {{\n                \"id\": {},\n                \"ok\": true\n        }},\n",
+            i
+        ));
+    }
+    source.push_str("        null\n]\n");
+
+    source
+}
+
+fn lex_all(source: &str, options: LexerOptions) -> usize {
+    let reader = MemoryReader::new(source.as_bytes()).unwrap();
+
+    Lexer::with_options(reader, options)
+        .unwrap()
+        .into_iter()
+        .count()
+}
+
+#[bench]
+fn bench_indented(b: &mut Bencher) {
+    let source = indented_source();
+
+    b.iter(|| lex_all(&source, LexerOptions::default()));
+}
+
+#[bench]
+fn bench_indented_coalesced(b: &mut Bencher) {
+    let source = indented_source();
+
+    b.iter(|| {
+        let options = LexerOptions {
+            coalesce_whitespace: true,
+            ..LexerOptions::default()
+        };
+
+        lex_all(&source, options)
+    });
+}
+
+#[bench]
+fn bench_key_heavy(b: &mut Bencher) {
+    let mut source = String::from("{");
+    for i in 0..4096 {
+        source.push_str(&format!("\"property_name_{}\": \"value_{}\", ", i, i));
+    }
+    source.push_str("\"last\": null}");
+
+    b.iter(|| lex_all(&source, LexerOptions::default()));
+}
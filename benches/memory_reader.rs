@@ -0,0 +1,42 @@
+#![feature(test)]
+
+extern crate test;
+
+use json::input_reader::{MemoryReader, ReadInput};
+use test::Bencher;
+
+const SOURCE: &[u8] = include_bytes!("../example.json");
+
+fn read_all(source: &[u8]) -> usize {
+    let mut reader = MemoryReader::new(source).unwrap();
+
+    reader.input_reader().count()
+}
+
+#[bench]
+fn bench_ascii(b: &mut Bencher) {
+    let source = SOURCE.repeat(256);
+
+    b.iter(|| read_all(&source));
+}
+
+#[bench]
+fn bench_unicode(b: &mut Bencher) {
+    let mut source = SOURCE.repeat(256);
+    source.extend_from_slice("\"ö\"".as_bytes());
+
+    b.iter(|| read_all(&source));
+}
+
+#[bench]
+fn bench_whitespace_run(b: &mut Bencher) {
+    let mut source = Vec::new();
+    for depth in 0..1024 {
+        source.push(b'\n');
+        source.resize(source.len() + depth % 32 * 4, b' ');
+    }
+    source.push(b'x');
+    let reader = MemoryReader::new(&source[..]).unwrap();
+
+    b.iter(|| reader.whitespace_run());
+}
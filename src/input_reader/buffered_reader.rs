@@ -1,6 +1,6 @@
-use std::{cmp, io, mem, str};
+use std::{char, cmp, io, mem, str};
 
-use super::{Error, ReadInput, Result};
+use super::{Error, Progress, ReadInput, Result};
 
 pub const BUF_READER_CAPACITY: usize = 16;
 
@@ -39,8 +39,14 @@ pub struct BufferedReader<R> {
     buf: Box<[u8]>,
     pos: usize,
     cap: usize,
+    offset: usize,
+    eof: bool,
 
-    chars: [Option<char>; BUF_READER_CAPACITY],
+    lossy: bool,
+    chars: Box<[Option<(char, usize)>]>,
+    last: Option<char>,
+
+    progress: Option<Progress>,
 }
 
 impl<R: io::Read> BufferedReader<R> {
@@ -69,7 +75,69 @@ impl<R: io::Read> BufferedReader<R> {
         Self::with_capacity(BUF_READER_CAPACITY, source)
     }
 
-    fn with_capacity(cap: usize, inner: R) -> Result<Self> {
+    /// Creates a new `BufferedReader<R>` with a default buffer capacity that
+    /// replaces invalid UTF-8 sequences with [`U+FFFD REPLACEMENT CHARACTER`]
+    /// instead of failing, like [`String::from_utf8_lossy`] does.
+    ///
+    /// [`U+FFFD REPLACEMENT CHARACTER`]: std::char::REPLACEMENT_CHARACTER
+    ///
+    /// # Errors
+    ///
+    /// This function can fail only if reading from the input fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, BufferedReader, ReadInput};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let reader = BufferedReader::new_lossy(&b"j\xffon"[..])?;
+    ///
+    ///     assert_eq!(reader.peek(0), Some('j'));
+    ///     assert_eq!(reader.peek(1), Some('\u{fffd}'));
+    ///     assert_eq!(reader.peek(2), Some('o'));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new_lossy(source: R) -> Result<Self> {
+        Self::with_decoding(BUF_READER_CAPACITY, source, true)
+    }
+
+    /// Creates a new `BufferedReader<R>` able to peek at most `cap` characters
+    /// at once.
+    ///
+    /// The capacity bounds the memory used by the input reader, but also the
+    /// longest token it can hand over in one piece. Lexing a token that
+    /// doesn't fit fails with a [`TokenTooLong`] error rather than splitting
+    /// it, so `cap` should be at least as long as the longest expected token.
+    ///
+    /// [`TokenTooLong`]: crate::lexer::ErrorKind::TokenTooLong
+    ///
+    /// # Errors
+    ///
+    /// This function can fail if reading from the input fails or if the input
+    /// is not valid UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, BufferedReader, ReadInput};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let reader = BufferedReader::with_capacity(2, "json".as_bytes())?;
+    ///
+    ///     assert_eq!(reader.peek(1), Some('s'));
+    ///     assert_eq!(reader.peek(2), None);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_capacity(cap: usize, inner: R) -> Result<Self> {
+        Self::with_decoding(cap, inner, false)
+    }
+
+    fn with_decoding(cap: usize, inner: R, lossy: bool) -> Result<Self> {
         let mut buffer = Vec::with_capacity((cap + 1) * mem::size_of::<char>());
         unsafe {
             buffer.set_len(cap * mem::size_of::<char>());
@@ -81,54 +149,229 @@ impl<R: io::Read> BufferedReader<R> {
             buf: buffer.into_boxed_slice(),
             pos: 0,
             cap: 0,
+            offset: 0,
+            eof: false,
+
+            lossy,
+            chars: vec![None; cap].into_boxed_slice(),
+            last: None,
 
-            chars: [None; BUF_READER_CAPACITY],
+            progress: None,
         };
         buf_reader.fill_buf()?;
 
         Ok(buf_reader)
     }
 
+    /// Returns the number of characters this input reader can peek at once,
+    /// which is also the most it can consume in a single call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, BufferedReader};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let reader = BufferedReader::new("json".as_bytes())?;
+    ///
+    ///     assert_eq!(reader.capacity(), 16);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.chars.len()
+    }
+
+    /// Returns the characters this input reader can currently peek as a
+    /// string slice, which is cheaper than peeking them one by one.
+    ///
+    /// The window holds at most [`capacity`] characters and is only valid
+    /// until the next call to [`consume`]. With lossy decoding, the window
+    /// stops short of the first invalid UTF-8 sequence, since it can't be
+    /// borrowed as a string slice.
+    ///
+    /// [`capacity`]: BufferedReader::capacity
+    /// [`consume`]: ReadInput::consume
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, BufferedReader, ReadInput};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let mut reader = BufferedReader::new("json".as_bytes())?;
+    ///
+    ///     assert_eq!(reader.window_str(), "json");
+    ///     reader.consume(1)?;
+    ///     assert_eq!(reader.window_str(), "son");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn window_str(&self) -> &str {
+        let len = self
+            .chars
+            .iter()
+            .flatten()
+            .map(|&(_, len)| len)
+            .sum::<usize>();
+        let window = &self.buf[self.pos..self.pos + len];
+
+        match str::from_utf8(window) {
+            Ok(window) => window,
+            Err(err) => str::from_utf8(&window[..err.valid_up_to()]).unwrap_or_default(),
+        }
+    }
+
+    /// Registers a callback that is passed the number of bytes consumed so
+    /// far every time at least another `interval` bytes have been consumed.
+    ///
+    /// Without a registered callback, consuming input costs nothing extra. The
+    /// callback must be `Send` so that the input reader stays `Send`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, BufferedReader, ReadInput};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let mut reader = BufferedReader::new("json".as_bytes())?;
+    ///     reader.on_progress(2, |bytes| println!("consumed {} bytes", bytes));
+    ///
+    ///     reader.consume(4)?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn on_progress<F>(&mut self, interval: usize, f: F)
+    where
+        F: FnMut(usize) + Send + 'static,
+    {
+        self.progress = Some(Progress::new(interval, Box::new(f)));
+    }
+
     fn fill_buf(&mut self) -> Result<()> {
-        // Branch using `>=` instead of the more correct `==` to tell the
-        // compiler that the pos..cap slice is always valid.
-        if self.pos >= self.cap {
-            self.cap = self.inner.read(&mut self.buf)?;
+        self.decode()?;
+
+        // Only read once every buffered character has been handed out, so
+        // that input arriving slowly, as from a pipe, is never waited on
+        // while some is already available.
+        while self.peek(0).is_none() && !self.eof {
+            // An incomplete character may be left over from the last read,
+            // so its bytes are moved to the front to be completed.
+            self.buf.copy_within(self.pos..self.cap, 0);
+            self.offset += self.pos;
+            self.cap -= self.pos;
             self.pos = 0;
+
+            let read = self
+                .inner
+                .read(&mut self.buf[self.cap..])
+                .map_err(|err| Error::refill_buffer(err, self.offset + self.cap))?;
+            self.cap += read;
+            self.eof = read == 0;
+
+            self.decode()?;
         }
 
-        let buf = &self.buf[self.pos..self.cap];
-        let str = str::from_utf8(buf)?;
+        Ok(())
+    }
 
-        let mut chars = str.chars();
-        self.chars.iter_mut().for_each(|c| *c = chars.next());
+    fn decode(&mut self) -> Result<()> {
+        let mut buf = &self.buf[self.pos..self.cap];
+        // A character split across reads is decoded once it has been read
+        // whole.
+        if !self.eof {
+            buf = &buf[..buf.len() - incomplete_tail(buf)];
+        }
+
+        if self.lossy {
+            let mut chars = buf.utf8_chunks().flat_map(|chunk| {
+                let valid = chunk.valid().chars().map(|c| (c, c.len_utf8()));
+                let invalid = Some(chunk.invalid().len())
+                    .filter(|&len| len > 0)
+                    .map(|len| (char::REPLACEMENT_CHARACTER, len));
+
+                valid.chain(invalid)
+            });
+            self.chars.iter_mut().for_each(|c| *c = chars.next());
+        } else {
+            let mut chars = str::from_utf8(buf)?.chars().map(|c| (c, c.len_utf8()));
+            self.chars.iter_mut().for_each(|c| *c = chars.next());
+        }
 
         Ok(())
     }
 }
 
+/// Returns the length of the UTF-8 sequence cut off at the end of `buf`, if
+/// any.
+fn incomplete_tail(buf: &[u8]) -> usize {
+    for (i, &byte) in buf.iter().rev().take(3).enumerate() {
+        let width = match byte {
+            0x00..=0x7f => return 0,
+            0x80..=0xbf => continue,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            _ => 4,
+        };
+
+        return if width > i + 1 { i + 1 } else { 0 };
+    }
+
+    0
+}
+
 impl<R: io::Read> ReadInput for BufferedReader<R> {
     fn peek(&self, k: usize) -> Option<char> {
-        self.chars.get(k).copied().flatten()
+        self.chars.get(k).copied().flatten().map(|(c, _)| c)
     }
 
     fn consume(&mut self, k: usize) -> Result<()> {
-        // TODO: Write tests for erroring on buffer overconsumption
-        if k > self.chars.len() {
-            return Err(Error::overconsume_buffer(k));
+        if k > self.capacity() {
+            return Err(Error::overconsume_buffer(k, self.capacity()));
         }
 
-        let len = self
-            .chars
-            .iter()
-            .take(k)
-            .filter_map(|c| c.map(char::len_utf8))
-            .sum::<usize>();
-        self.pos = cmp::min(self.pos + len, self.cap);
-        self.fill_buf()?;
+        // The window may hold fewer than `k` characters before the end of
+        // input, in which case the rest are consumed after refilling it.
+        let (mut left, mut len) = (k, 0);
+        while left > 0 && self.peek(0).is_some() {
+            let consumed = self.chars.iter().take(left).flatten();
+            if let Some(&(c, _)) = consumed.clone().last() {
+                self.last = Some(c);
+            }
+
+            let consumed_len = consumed.clone().map(|&(_, len)| len).sum::<usize>();
+            left -= consumed.count();
+            len += consumed_len;
+            self.pos = cmp::min(self.pos + consumed_len, self.cap);
+            self.fill_buf()?;
+        }
+
+        if let Some(progress) = &mut self.progress {
+            progress.advance(len);
+        }
 
         Ok(())
     }
+
+    fn last_consumed(&self) -> Option<char> {
+        self.last
+    }
+
+    fn bytes_consumed(&self) -> usize {
+        self.offset + self.pos
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        // Only known once the rest of the input has been read and fits in
+        // the window.
+        let window = self.chars.iter().flatten();
+        let len = window.clone().map(|&(_, len)| len).sum::<usize>();
+
+        (self.eof && self.pos + len == self.cap).then(|| window.count())
+    }
 }
 
 #[cfg(test)]
@@ -192,6 +435,138 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_capacity() -> Result<()> {
+        let buf_reader = BufferedReader::new(SOURCE)?;
+        assert_eq!(buf_reader.capacity(), BUF_READER_CAPACITY);
+
+        let mut buf_reader = BufferedReader::with_capacity(2, SOURCE)?;
+        assert_eq!(buf_reader.capacity(), 2);
+        assert_eq!(buf_reader.peek(1), Some('s'));
+        assert_eq!(buf_reader.peek(2), None);
+
+        let err = buf_reader.consume(3).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "input reader tried consuming 3 characters when its buffer holds only 2 characters"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_overconsume_window() -> Result<()> {
+        let mut buf_reader = BufferedReader::with_capacity(2, "abcdéfgh".as_bytes())?;
+
+        for c in ['c', 'é', 'g'] {
+            buf_reader.consume(2)?;
+            assert_eq!(buf_reader.peek(0), Some(c));
+        }
+
+        let err = buf_reader.consume(3).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "input reader tried consuming 3 characters when its buffer holds only 2 characters"
+        );
+        assert_eq!(buf_reader.peek(0), Some('g'));
+        buf_reader.consume(2)?;
+        assert_eq!(buf_reader.bytes_consumed(), 9);
+        assert!(buf_reader.has_reached_eof());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_read_while_buffered() -> Result<()> {
+        // Reading again would block, as on a pipe with nothing more written.
+        struct Blocking(Option<&'static [u8]>);
+
+        impl io::Read for Blocking {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                match self.0.take() {
+                    Some(mut source) => source.read(buf),
+                    None => panic!("read() called while characters were buffered"),
+                }
+            }
+        }
+
+        let mut buf_reader = BufferedReader::new(Blocking(Some(b"[1]\n")))?;
+        assert_eq!(buf_reader.window_str(), "[1]\n");
+        buf_reader.consume(1)?;
+        buf_reader.consume(2)?;
+        assert_eq!(buf_reader.peek(0), Some('\n'));
+        assert_eq!(buf_reader.bytes_consumed(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_short_reads() -> Result<()> {
+        struct OneByte(&'static [u8]);
+
+        impl io::Read for OneByte {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let len = cmp::min(1, self.0.len());
+                (&self.0[..len]).read(buf)?;
+                self.0 = &self.0[len..];
+
+                Ok(len)
+            }
+        }
+
+        let mut buf_reader = BufferedReader::with_capacity(3, OneByte("jsön".as_bytes()))?;
+        assert_eq!(buf_reader.window_str(), "j");
+        buf_reader.consume(3)?;
+        assert_eq!(buf_reader.last_consumed(), Some('ö'));
+        assert_eq!(buf_reader.window_str(), "n");
+        assert_eq!(buf_reader.input_reader().collect::<String>(), "n");
+
+        let mut buf_reader = BufferedReader::new(OneByte(&b"j\xc3"[..]))?;
+        assert!(buf_reader.consume(1).is_err());
+        let mut buf_reader = BufferedReader::new_lossy(OneByte(&b"j\xc3"[..]))?;
+        buf_reader.consume(1)?;
+        assert_eq!(buf_reader.peek(0), Some('\u{fffd}'));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_utf8() -> Result<()> {
+        assert!(BufferedReader::new(&b"j\xffon"[..]).is_err());
+
+        let mut buf_reader = BufferedReader::new_lossy(&b"j\xff\xfeo\xe2\x82n"[..])?;
+        let mut input_reader = buf_reader.input_reader();
+
+        assert_eq!(input_reader.next(), Some('j'));
+        assert_eq!(input_reader.next(), Some('\u{fffd}'));
+        assert_eq!(input_reader.next(), Some('\u{fffd}'));
+        assert_eq!(input_reader.next(), Some('o'));
+        assert_eq!(input_reader.next(), Some('\u{fffd}'));
+        assert_eq!(input_reader.next(), Some('n'));
+        assert_eq!(input_reader.next(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_last_consumed() -> Result<()> {
+        let mut buf_reader = BufferedReader::new(SOURCE)?;
+
+        assert_eq!(buf_reader.last_consumed(), None);
+        buf_reader.consume(0)?;
+        assert_eq!(buf_reader.last_consumed(), None);
+        buf_reader.consume(1)?;
+        assert_eq!(buf_reader.last_consumed(), Some('j'));
+        buf_reader.consume(2)?;
+        assert_eq!(buf_reader.last_consumed(), Some('o'));
+        buf_reader.consume(0)?;
+        assert_eq!(buf_reader.last_consumed(), Some('o'));
+        buf_reader.consume(5)?;
+        assert_eq!(buf_reader.last_consumed(), Some('n'));
+
+        Ok(())
+    }
+
     #[test]
     fn test_next() -> Result<()> {
         let mut buf_reader = BufferedReader::new(SOURCE)?;
@@ -205,4 +580,49 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_window_str() -> Result<()> {
+        let mut buf_reader = BufferedReader::with_capacity(4, "jsön!".as_bytes())?;
+
+        assert_eq!(buf_reader.window_str(), "jsön");
+        buf_reader.consume(2)?;
+        assert_eq!(buf_reader.window_str(), "ön!");
+        buf_reader.consume(3)?;
+        assert_eq!(buf_reader.window_str(), "");
+
+        let buf_reader = BufferedReader::new_lossy(&b"js\xffon"[..])?;
+        assert_eq!(buf_reader.window_str(), "js");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_refill_error_offset() -> Result<()> {
+        use std::error::Error as _;
+
+        struct FailSecond(Option<&'static [u8]>);
+
+        impl io::Read for FailSecond {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                match self.0.take() {
+                    Some(mut source) => source.read(buf),
+                    None => Err(io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed")),
+                }
+            }
+        }
+
+        let mut buf_reader = BufferedReader::with_capacity(2, FailSecond(Some(SOURCE)))?;
+        buf_reader.consume(2)?;
+        let err = buf_reader.consume(2).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "pipe closed (while refilling buffer at offset 4)"
+        );
+        let io_err = err.source().and_then(|err| err.downcast_ref::<io::Error>());
+        assert_eq!(io_err.map(io::Error::kind), Some(io::ErrorKind::BrokenPipe));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,214 @@
+use alloc::vec::Vec;
+
+use super::{ReadInput, Result};
+
+/// The `ChainReader<R>` struct concatenates multiple input readers.
+///
+/// This input reader presents a sequence of input readers as one continuous
+/// stream of characters, much like [`io::Chain`] does for bytes. Once an
+/// input reader reaches its end of input, reading continues with the next
+/// one, and peeking past the end of one input reader looks into the next.
+///
+/// Peeking only looks into the next input reader once the current one is
+/// known to hold nothing beyond what it can currently peek, that is once it
+/// has reached its end of input or its [`remaining`] count is all peekable.
+/// Past the window of an input reader such as a [`BufferedReader`] with more
+/// input to read, peeking returns `None` until enough has been consumed.
+///
+/// [`remaining`]: ReadInput::remaining
+/// [`BufferedReader`]: crate::input_reader::BufferedReader
+///
+/// Heterogeneous input readers can be chained by boxing them as
+/// `Box<dyn ReadInput>`.
+///
+/// [`io::Chain`]: std::io::Chain
+///
+/// # Examples
+///
+/// ```
+/// use json::input_reader::{self, ChainReader, MemoryReader, ReadInput};
+///
+/// fn main() -> input_reader::Result<()> {
+///     let mut reader = ChainReader::new(vec![
+///         MemoryReader::new("js".as_bytes())?,
+///         MemoryReader::new("on".as_bytes())?,
+///     ]);
+///
+///     assert_eq!(reader.peek(2), Some('o'));
+///     reader.consume(3)?;
+///     assert_eq!(reader.peek(0), Some('n'));
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ChainReader<R> {
+    readers: Vec<R>,
+    current: usize,
+    last: Option<char>,
+}
+
+impl<R: ReadInput> ChainReader<R> {
+    /// Creates a new `ChainReader<R>` reading from `readers` in order.
+    pub fn new<I>(readers: I) -> Self
+    where
+        I: IntoIterator<Item = R>,
+    {
+        Self {
+            readers: readers.into_iter().collect(),
+            current: 0,
+            last: None,
+        }
+    }
+}
+
+fn available<R: ReadInput>(reader: &R, max: usize) -> usize {
+    (0..max).take_while(|&k| reader.peek(k).is_some()).count()
+}
+
+impl<R: ReadInput> ReadInput for ChainReader<R> {
+    fn peek(&self, mut k: usize) -> Option<char> {
+        for reader in &self.readers[self.current..] {
+            let available = available(reader, k + 1);
+            if available > k {
+                return reader.peek(k);
+            }
+            // Running out of peekable characters only ends the window of an
+            // input reader that may still have more to read.
+            if available > 0 && reader.remaining() != Some(available) {
+                return None;
+            }
+            k -= available;
+        }
+
+        None
+    }
+
+    fn consume(&mut self, mut k: usize) -> Result<()> {
+        while k > 0 {
+            let reader = match self.readers.get_mut(self.current) {
+                Some(reader) => reader,
+                None => break,
+            };
+            if reader.has_reached_eof() {
+                self.current += 1;
+                continue;
+            }
+
+            let available = available(reader, k);
+            reader.consume(available)?;
+            self.last = reader.last_consumed();
+            k -= available;
+        }
+
+        Ok(())
+    }
+
+    fn last_consumed(&self) -> Option<char> {
+        self.last
+    }
+
+    fn bytes_consumed(&self) -> usize {
+        self.readers.iter().map(ReadInput::bytes_consumed).sum()
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        self.readers[self.current..]
+            .iter()
+            .map(ReadInput::remaining)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::input_reader::MemoryReader;
+
+    fn chain(sources: &[&str]) -> Result<ChainReader<MemoryReader>> {
+        let readers = sources
+            .iter()
+            .map(|source| MemoryReader::new(source.as_bytes()))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(ChainReader::new(readers))
+    }
+
+    #[test]
+    fn test_peek() -> Result<()> {
+        let chain_reader = chain(&["jso", "", "n"])?;
+
+        assert_eq!(chain_reader.peek(0), Some('j'));
+        assert_eq!(chain_reader.peek(2), Some('o'));
+        assert_eq!(chain_reader.peek(3), Some('n'));
+        assert_eq!(chain_reader.peek(4), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_consume() -> Result<()> {
+        let mut chain_reader = chain(&["", "jso", "", "n"])?;
+
+        chain_reader.consume(2)?;
+        assert_eq!(chain_reader.peek(0), Some('o'));
+        assert_eq!(chain_reader.peek(1), Some('n'));
+        chain_reader.consume(2)?;
+        assert_eq!(chain_reader.last_consumed(), Some('n'));
+        assert!(chain_reader.has_reached_eof());
+        chain_reader.consume(1)?;
+        assert!(chain_reader.has_reached_eof());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_next() -> Result<()> {
+        use crate::input_reader::BufferedReader;
+
+        let mut chain_reader = ChainReader::new(vec![
+            Box::new(BufferedReader::new("jso".as_bytes())?) as Box<dyn ReadInput>,
+            Box::new(MemoryReader::new("n".as_bytes())?),
+        ]);
+        let input_reader = chain_reader.input_reader();
+
+        assert_eq!(input_reader.collect::<String>(), "json");
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_peek_past_window() -> Result<()> {
+        use crate::input_reader::BufferedReader;
+
+        let mut chain_reader = ChainReader::new(vec![
+            Box::new(BufferedReader::with_capacity(4, "abcdefgh".as_bytes())?)
+                as Box<dyn ReadInput>,
+            Box::new(MemoryReader::new("XYZ".as_bytes())?),
+        ]);
+
+        assert_eq!(chain_reader.peek(3), Some('d'));
+        assert_eq!(chain_reader.peek(4), None);
+        assert_eq!(chain_reader.peek_until('X'), None);
+
+        // The window doesn't tell whether more input follows until the end of
+        // input has been read.
+        chain_reader.consume(4)?;
+        assert_eq!(chain_reader.peek(0), Some('e'));
+        assert_eq!(chain_reader.peek(3), Some('h'));
+        assert_eq!(chain_reader.peek(4), None);
+
+        chain_reader.consume(4)?;
+        assert_eq!(chain_reader.peek(0), Some('X'));
+        assert_eq!(chain_reader.last_consumed(), Some('h'));
+        assert_eq!(chain_reader.peek_until('Z').as_deref(), Some("XY"));
+
+        let input_reader = chain_reader.input_reader();
+        assert_eq!(input_reader.collect::<String>(), "XYZ");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use super::{Error, ReadInput, Result};
+
+/// The `LimitReader<R>` struct caps the number of characters consumed from
+/// another input reader.
+///
+/// Peeking is unaffected, but consuming fails with a limit error as soon as
+/// it would take the total number of consumed characters past the limit.
+/// Unlike per-token limits, this caps the input as a whole, whatever tokens
+/// it is made of.
+///
+/// # Examples
+///
+/// ```
+/// use json::input_reader::{self, LimitReader, MemoryReader, ReadInput};
+///
+/// fn main() -> input_reader::Result<()> {
+///     let reader = MemoryReader::new("json".as_bytes())?;
+///     let mut reader = LimitReader::new(reader, 3);
+///
+///     reader.consume(3)?;
+///     assert_eq!(reader.peek(0), Some('n'));
+///     assert!(reader.consume(1).unwrap_err().is_limit_exceeded());
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct LimitReader<R> {
+    inner: R,
+    limit: usize,
+    consumed: usize,
+}
+
+impl<R: ReadInput> LimitReader<R> {
+    /// Creates a new `LimitReader<R>` allowing at most `limit` characters to
+    /// be consumed from `inner`.
+    pub fn new(inner: R, limit: usize) -> Self {
+        Self {
+            inner,
+            limit,
+            consumed: 0,
+        }
+    }
+}
+
+impl<R: ReadInput> ReadInput for LimitReader<R> {
+    fn peek(&self, k: usize) -> Option<char> {
+        self.inner.peek(k)
+    }
+
+    fn consume(&mut self, k: usize) -> Result<()> {
+        // Consuming past the end of input doesn't count towards the limit.
+        let available = (0..k).take_while(|&i| self.inner.peek(i).is_some()).count();
+        if self.consumed + available > self.limit {
+            return Err(Error::exceed_limit(self.limit));
+        }
+
+        self.inner.consume(k)?;
+        self.consumed += available;
+
+        Ok(())
+    }
+
+    fn last_consumed(&self) -> Option<char> {
+        self.inner.last_consumed()
+    }
+
+    fn bytes_consumed(&self) -> usize {
+        self.inner.bytes_consumed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{input_reader::MemoryReader, parser};
+
+    const SOURCE: &[u8] = "json".as_bytes();
+
+    #[test]
+    fn test_consume() -> Result<()> {
+        let mut limit_reader = LimitReader::new(MemoryReader::new(SOURCE)?, 4);
+
+        limit_reader.consume(1)?;
+        limit_reader.consume(3)?;
+        limit_reader.consume(2)?;
+        assert!(limit_reader.has_reached_eof());
+
+        let mut limit_reader = LimitReader::new(MemoryReader::new(SOURCE)?, 2);
+
+        limit_reader.consume(1)?;
+        assert!(limit_reader.consume(2).unwrap_err().is_limit_exceeded());
+        assert_eq!(limit_reader.peek(0), Some('s'));
+        limit_reader.consume(1)?;
+        assert_eq!(limit_reader.last_consumed(), Some('s'));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_limit_exceeded_mid_parse() -> Result<()> {
+        let reader = MemoryReader::new("[1, 2, 3]".as_bytes())?;
+        assert!(parser::validate(LimitReader::new(reader, 9), 128).is_ok());
+
+        let reader = MemoryReader::new("[1, 2, 3]".as_bytes())?;
+        let err = parser::validate(LimitReader::new(reader, 5), 128).unwrap_err();
+        assert_eq!(err.to_string(), "input exceeds the limit of 5 characters");
+        assert!(!err.recoverable());
+
+        Ok(())
+    }
+}
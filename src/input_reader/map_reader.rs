@@ -0,0 +1,118 @@
+use core::fmt;
+
+use super::{ReadInput, Result};
+
+/// The `MapReader<R, F>` struct maps the characters of another input reader.
+///
+/// Every character peeked through a `MapReader<R, F>` is passed through the
+/// mapping function first. Since each input character maps to exactly one
+/// output character, positions and consume counts are the same as those of
+/// the wrapped input reader.
+///
+/// # Examples
+///
+/// ```
+/// use json::input_reader::{self, MapReader, MemoryReader, ReadInput};
+///
+/// fn main() -> input_reader::Result<()> {
+///     let reader = MemoryReader::new("NULL".as_bytes())?;
+///     let mut reader = MapReader::new(reader, |c| c.to_ascii_lowercase());
+///
+///     assert_eq!(reader.peek(0), Some('n'));
+///     reader.consume(1)?;
+///     assert_eq!(reader.peek(0), Some('u'));
+///
+///     Ok(())
+/// }
+/// ```
+pub struct MapReader<R, F> {
+    inner: R,
+    f: F,
+}
+
+impl<R, F> MapReader<R, F>
+where
+    R: ReadInput,
+    F: Fn(char) -> char,
+{
+    /// Creates a new `MapReader<R, F>` applying `f` to every character of
+    /// `inner`.
+    pub fn new(inner: R, f: F) -> Self {
+        Self { inner, f }
+    }
+}
+
+impl<R, F> ReadInput for MapReader<R, F>
+where
+    R: ReadInput,
+    F: Fn(char) -> char,
+{
+    fn peek(&self, k: usize) -> Option<char> {
+        self.inner.peek(k).map(&self.f)
+    }
+
+    fn consume(&mut self, k: usize) -> Result<()> {
+        self.inner.consume(k)
+    }
+
+    fn last_consumed(&self) -> Option<char> {
+        self.inner.last_consumed().map(&self.f)
+    }
+
+    fn bytes_consumed(&self) -> usize {
+        self.inner.bytes_consumed()
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        self.inner.remaining()
+    }
+}
+
+impl<R: fmt::Debug, F> fmt::Debug for MapReader<R, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapReader")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::input_reader::MemoryReader;
+
+    const SOURCE: &[u8] = "json".as_bytes();
+
+    #[test]
+    fn test_peek() -> Result<()> {
+        let map_reader = MapReader::new(MemoryReader::new(SOURCE)?, |c| c.to_ascii_uppercase());
+
+        assert_eq!(map_reader.peek(0), Some('J'));
+        assert_eq!(map_reader.peek(3), Some('N'));
+        assert_eq!(map_reader.peek(4), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_last_consumed() -> Result<()> {
+        let mut map_reader = MapReader::new(MemoryReader::new(SOURCE)?, |c| c.to_ascii_uppercase());
+
+        assert_eq!(map_reader.last_consumed(), None);
+        map_reader.consume(2)?;
+        assert_eq!(map_reader.last_consumed(), Some('S'));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_next() -> Result<()> {
+        let mut map_reader = MapReader::new(MemoryReader::new(SOURCE)?, |c| c.to_ascii_uppercase());
+        let input_reader = map_reader.input_reader();
+
+        assert_eq!(input_reader.collect::<String>(), "JSON");
+
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
-use std::{cmp, io, str};
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::{cmp, str};
 
-use super::{ReadInput, Result};
+use super::{Progress, ReadBytes, ReadInput, Result};
 
 /// The `MemoryReader` struct provides in-memory whole input reading.
 ///
@@ -11,7 +12,8 @@ use super::{ReadInput, Result};
 ///
 /// A `MemoryReader` reads the whole input in memory in a fixed-size
 /// heap-allocated buffer. That means only one read call, but a potential
-/// exhaustion of available memory.
+/// exhaustion of available memory. Pure ASCII input is kept as bytes, using
+/// one byte per character instead of four.
 ///
 /// # Examples
 ///
@@ -34,8 +36,47 @@ use super::{ReadInput, Result};
 /// ```
 #[derive(Debug)]
 pub struct MemoryReader {
-    buf: Box<[char]>,
+    buf: Buf,
     pos: usize,
+    bytes: usize,
+
+    progress: Option<Progress>,
+}
+
+#[derive(Debug)]
+enum Buf {
+    Ascii(Box<[u8]>),
+    Unicode(Box<[char]>),
+}
+
+impl Buf {
+    fn get(&self, idx: usize) -> Option<char> {
+        match self {
+            Buf::Ascii(bytes) => bytes.get(idx).copied().map(char::from),
+            Buf::Unicode(chars) => chars.get(idx).copied(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Buf::Ascii(bytes) => bytes.len(),
+            Buf::Unicode(chars) => chars.len(),
+        }
+    }
+
+    fn byte_len(&self, start: usize, end: usize) -> usize {
+        match self {
+            Buf::Ascii(_bytes) => end - start,
+            Buf::Unicode(chars) => chars[start..end].iter().map(|c| c.len_utf8()).sum(),
+        }
+    }
+
+    fn text(&self, start: usize, end: usize) -> String {
+        match self {
+            Buf::Ascii(bytes) => bytes[start..end].iter().copied().map(char::from).collect(),
+            Buf::Unicode(chars) => chars[start..end].iter().collect(),
+        }
+    }
 }
 
 impl MemoryReader {
@@ -59,39 +100,330 @@ impl MemoryReader {
     /// ```
     pub fn new<R>(mut source: R) -> Result<Self>
     where
-        R: io::Read,
+        R: ReadBytes,
     {
         let mut buffer = Vec::new();
-        source.read_to_end(&mut buffer)?;
+        source.read_all(&mut buffer)?;
+        if buffer.is_ascii() {
+            return Ok(Self::from_ascii(buffer));
+        }
         let buffer = str::from_utf8(&buffer)?;
-        let buffer = buffer.chars().collect::<Vec<_>>();
 
-        Ok(Self {
-            buf: buffer.into_boxed_slice(),
+        Ok(Self::from_chars(buffer.chars()))
+    }
+
+    /// Creates a new `MemoryReader` by reading the whole input, replacing
+    /// invalid UTF-8 sequences with [`U+FFFD REPLACEMENT CHARACTER`] instead
+    /// of failing, like [`String::from_utf8_lossy`] does.
+    ///
+    /// [`U+FFFD REPLACEMENT CHARACTER`]: std::char::REPLACEMENT_CHARACTER
+    ///
+    /// # Errors
+    ///
+    /// This associated function can fail only if reading from the input
+    /// fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, MemoryReader, ReadInput};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let reader = MemoryReader::new_lossy(&b"j\xffon"[..])?;
+    ///
+    ///     assert_eq!(reader.peek(0), Some('j'));
+    ///     assert_eq!(reader.peek(1), Some('\u{fffd}'));
+    ///     assert_eq!(reader.peek(2), Some('o'));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn new_lossy<R>(mut source: R) -> Result<Self>
+    where
+        R: ReadBytes,
+    {
+        let mut buffer = Vec::new();
+        source.read_all(&mut buffer)?;
+        if buffer.is_ascii() {
+            return Ok(Self::from_ascii(buffer));
+        }
+        let buffer = String::from_utf8_lossy(&buffer);
+
+        Ok(Self::from_chars(buffer.chars()))
+    }
+
+    /// Returns the characters not consumed yet as a string.
+    ///
+    /// The string is rebuilt from the internal buffer on every call, so this
+    /// allocates and copies the whole remaining input. Unicode input is also
+    /// re-encoded from characters into UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, MemoryReader, ReadInput};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let mut reader = MemoryReader::new("json".as_bytes())?;
+    ///     reader.consume(2)?;
+    ///
+    ///     assert_eq!(reader.remaining_str(), "on");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn remaining_str(&self) -> String {
+        self.buf.text(self.pos, self.buf.len())
+    }
+
+    /// Consumes up to `k` characters like [`consume`] does, returning them as
+    /// a string.
+    ///
+    /// The consumed characters are encoded into the string in one go, which
+    /// is cheaper than peeking and consuming them one at a time.
+    ///
+    /// [`consume`]: ReadInput::consume
+    ///
+    /// # Errors
+    ///
+    /// This function doesn't currently fail, but returns a [`Result`] like
+    /// [`consume`] does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, MemoryReader, ReadInput};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let mut reader = MemoryReader::new("-12.5,".as_bytes())?;
+    ///
+    ///     assert_eq!(reader.consume_str(5)?, "-12.5");
+    ///     assert_eq!(reader.peek(0), Some(','));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn consume_str(&mut self, k: usize) -> Result<String> {
+        let end = cmp::min(self.pos + k, self.buf.len());
+        let text = self.buf.text(self.pos, end);
+        self.consume(k)?;
+
+        Ok(text)
+    }
+
+    /// Consumes up to `k` characters like [`consume`] does, returning how many
+    /// were actually consumed.
+    ///
+    /// [`consume`] saturates at the end of input, so the returned count is
+    /// less than `k` exactly when the input ended prematurely.
+    ///
+    /// [`consume`]: ReadInput::consume
+    ///
+    /// # Errors
+    ///
+    /// This function doesn't currently fail, but returns a [`Result`] like
+    /// [`consume`] does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, MemoryReader};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let mut reader = MemoryReader::new("json".as_bytes())?;
+    ///
+    ///     assert_eq!(reader.consume_checked(3)?, 3);
+    ///     assert_eq!(reader.consume_checked(3)?, 1);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn consume_checked(&mut self, k: usize) -> Result<usize> {
+        let start = self.pos;
+        self.consume(k)?;
+
+        Ok(self.pos - start)
+    }
+
+    /// Returns the position of the next character to be read, counted in
+    /// characters from the start of the input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, MemoryReader, ReadInput};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let mut reader = MemoryReader::new("json".as_bytes())?;
+    ///     reader.consume(3)?;
+    ///
+    ///     assert_eq!(reader.position(), 3);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub const fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Moves to the character at `pos`, counted in characters from the start
+    /// of the input, either backward or forward.
+    ///
+    /// Like [`consume`], this saturates at the end of input, so a `pos` past
+    /// it moves to the end. [`bytes_consumed`] then counts the bytes up to
+    /// the new position, while a callback registered with [`on_progress`]
+    /// only ever sees input passed over by [`consume`].
+    ///
+    /// [`consume`]: ReadInput::consume
+    /// [`bytes_consumed`]: ReadInput::bytes_consumed
+    /// [`on_progress`]: MemoryReader::on_progress
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, MemoryReader, ReadInput};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let mut reader = MemoryReader::new("json".as_bytes())?;
+    ///     reader.consume(3)?;
+    ///     reader.set_position(1);
+    ///
+    ///     assert_eq!(reader.peek(0), Some('s'));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_position(&mut self, pos: usize) {
+        self.pos = cmp::min(pos, self.buf.len());
+        self.bytes = self.buf.byte_len(0, self.pos);
+    }
+
+    /// Registers a callback that is passed the number of bytes consumed so
+    /// far every time at least another `interval` bytes have been consumed.
+    ///
+    /// Bytes are counted in UTF-8, as in the original input. Without a
+    /// registered callback, consuming input costs nothing extra. The callback
+    /// must be `Send` so that the input reader stays `Send`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, MemoryReader, ReadInput};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let mut reader = MemoryReader::new("json".as_bytes())?;
+    ///     reader.on_progress(2, |bytes| println!("consumed {} bytes", bytes));
+    ///
+    ///     reader.consume(4)?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn on_progress<F>(&mut self, interval: usize, f: F)
+    where
+        F: FnMut(usize) + Send + 'static,
+    {
+        self.progress = Some(Progress::new(interval, Box::new(f)));
+    }
+
+    fn from_ascii(buffer: Vec<u8>) -> Self {
+        Self {
+            buf: Buf::Ascii(buffer.into_boxed_slice()),
+            pos: 0,
+            bytes: 0,
+
+            progress: None,
+        }
+    }
+
+    fn from_chars(chars: str::Chars<'_>) -> Self {
+        Self {
+            buf: Buf::Unicode(chars.collect()),
             pos: 0,
-        })
+            bytes: 0,
+
+            progress: None,
+        }
     }
 }
 
 impl ReadInput for MemoryReader {
     fn peek(&self, k: usize) -> Option<char> {
-        self.buf.get(self.pos + k).copied()
+        self.buf.get(self.pos + k)
     }
 
     fn consume(&mut self, k: usize) -> Result<()> {
+        let start = self.pos;
         self.pos = cmp::min(self.pos + k, self.buf.len());
 
+        let len = self.buf.byte_len(start, self.pos);
+        self.bytes += len;
+        if let Some(progress) = &mut self.progress {
+            progress.advance(len);
+        }
+
         Ok(())
     }
+
+    fn last_consumed(&self) -> Option<char> {
+        self.buf.get(self.pos.checked_sub(1)?)
+    }
+
+    fn bytes_consumed(&self) -> usize {
+        self.bytes
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        Some(self.buf.len() - self.pos)
+    }
+
+    #[cfg(feature = "fast-scan")]
+    fn whitespace_run(&self) -> usize {
+        match &self.buf {
+            Buf::Ascii(bytes) => ascii_whitespace_run(&bytes[self.pos..]),
+            Buf::Unicode(chars) => chars[self.pos..]
+                .iter()
+                .take_while(|c| matches!(c, ' ' | '\t' | '\n' | '\r'))
+                .count(),
+        }
+    }
+}
+
+/// Counts the leading whitespace bytes, skipping whole words of spaces, such
+/// as those indenting pretty-printed documents, eight bytes at a time.
+#[cfg(feature = "fast-scan")]
+fn ascii_whitespace_run(bytes: &[u8]) -> usize {
+    const SPACES: u64 = u64::from_ne_bytes([b' '; 8]);
+
+    let mut run = 0;
+    loop {
+        while let Some(word) = bytes.get(run..run + 8) {
+            let mut word_bytes = [0; 8];
+            word_bytes.copy_from_slice(word);
+            if u64::from_ne_bytes(word_bytes) != SPACES {
+                break;
+            }
+            run += 8;
+        }
+
+        match bytes.get(run) {
+            Some(b' ' | b'\t' | b'\n' | b'\r') => run += 1,
+            _ => return run,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "std")]
+    use std::io;
+
     const SOURCE: &[u8] = "json".as_bytes();
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_peek_empty() -> Result<()> {
         let mem_reader = MemoryReader::new(io::empty())?;
 
@@ -115,6 +447,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_consume_empty() -> Result<()> {
         let mut mem_reader = MemoryReader::new(io::empty())?;
 
@@ -146,6 +479,65 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_ascii_and_unicode_are_eq() -> Result<()> {
+        let mut ascii_reader = MemoryReader::new(SOURCE)?;
+        let mut unicode_reader = MemoryReader::new("jsön".as_bytes())?;
+
+        assert!(matches!(ascii_reader.buf, Buf::Ascii(_)));
+        assert!(matches!(unicode_reader.buf, Buf::Unicode(_)));
+
+        assert_eq!(ascii_reader.peek(3), unicode_reader.peek(3));
+        assert_eq!(ascii_reader.peek(4), unicode_reader.peek(4));
+        ascii_reader.consume(2)?;
+        unicode_reader.consume(2)?;
+        assert_eq!(ascii_reader.peek(0), Some('o'));
+        assert_eq!(unicode_reader.peek(0), Some('ö'));
+        ascii_reader.consume(5)?;
+        unicode_reader.consume(5)?;
+        assert!(ascii_reader.has_reached_eof());
+        assert!(unicode_reader.has_reached_eof());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_utf8() -> Result<()> {
+        assert!(MemoryReader::new(&b"j\xffon"[..]).is_err());
+
+        let mut mem_reader = MemoryReader::new_lossy(&b"j\xff\xfeo\xe2\x82n"[..])?;
+        let mut input_reader = mem_reader.input_reader();
+
+        assert_eq!(input_reader.next(), Some('j'));
+        assert_eq!(input_reader.next(), Some('\u{fffd}'));
+        assert_eq!(input_reader.next(), Some('\u{fffd}'));
+        assert_eq!(input_reader.next(), Some('o'));
+        assert_eq!(input_reader.next(), Some('\u{fffd}'));
+        assert_eq!(input_reader.next(), Some('n'));
+        assert_eq!(input_reader.next(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_last_consumed() -> Result<()> {
+        let mut mem_reader = MemoryReader::new(SOURCE)?;
+
+        assert_eq!(mem_reader.last_consumed(), None);
+        mem_reader.consume(0)?;
+        assert_eq!(mem_reader.last_consumed(), None);
+        mem_reader.consume(1)?;
+        assert_eq!(mem_reader.last_consumed(), Some('j'));
+        mem_reader.consume(2)?;
+        assert_eq!(mem_reader.last_consumed(), Some('o'));
+        mem_reader.consume(0)?;
+        assert_eq!(mem_reader.last_consumed(), Some('o'));
+        mem_reader.consume(5)?;
+        assert_eq!(mem_reader.last_consumed(), Some('n'));
+
+        Ok(())
+    }
+
     #[test]
     fn test_next() -> Result<()> {
         let mut mem_reader = MemoryReader::new(SOURCE)?;
@@ -159,4 +551,92 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_remaining_str() -> Result<()> {
+        for (source, rest) in [("json", "on"), ("jsön", "ön")] {
+            let mut mem_reader = MemoryReader::new(source.as_bytes())?;
+            assert_eq!(mem_reader.remaining_str(), source);
+
+            mem_reader.consume(2)?;
+            assert_eq!(mem_reader.remaining_str(), rest);
+            mem_reader.consume(5)?;
+            assert_eq!(mem_reader.remaining_str(), "");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_consume_str() -> Result<()> {
+        for (source, run, rest) in [("json", "js", "on"), ("jsön", "jsö", "n")] {
+            let mut mem_reader = MemoryReader::new(source.as_bytes())?;
+
+            assert_eq!(mem_reader.consume_str(0)?, "");
+            assert_eq!(mem_reader.consume_str(run.chars().count())?, run);
+            assert_eq!(mem_reader.last_consumed(), run.chars().last());
+            assert_eq!(mem_reader.remaining_str(), rest);
+            assert_eq!(mem_reader.consume_str(5)?, rest);
+            assert!(mem_reader.has_reached_eof());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_whitespace_run() -> Result<()> {
+        let indented = format!("\n{}\t\r\n{}x ", " ".repeat(21), " ".repeat(8));
+        for (source, run) in [("", 0), ("json", 0), ("  \n", 3), (indented.as_str(), 33)] {
+            let mem_reader = MemoryReader::new(source.as_bytes())?;
+            assert_eq!(mem_reader.whitespace_run(), run);
+        }
+
+        let mut mem_reader = MemoryReader::new(" \n ö ".as_bytes())?;
+        assert_eq!(mem_reader.whitespace_run(), 3);
+        mem_reader.consume(4)?;
+        assert_eq!(mem_reader.whitespace_run(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_consume_checked() -> Result<()> {
+        let mut mem_reader = MemoryReader::new(SOURCE)?;
+
+        assert_eq!(mem_reader.consume_checked(0)?, 0);
+        assert_eq!(mem_reader.consume_checked(2)?, 2);
+        assert_eq!(mem_reader.consume_checked(5)?, 2);
+        assert_eq!(mem_reader.last_consumed(), Some('n'));
+        assert_eq!(mem_reader.consume_checked(1)?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_position() -> Result<()> {
+        let mut mem_reader = MemoryReader::new(SOURCE)?;
+
+        mem_reader.set_position(3);
+        assert_eq!(mem_reader.position(), 3);
+        assert_eq!(mem_reader.peek(0), Some('n'));
+        assert_eq!(mem_reader.last_consumed(), Some('o'));
+
+        mem_reader.set_position(1);
+        assert_eq!(mem_reader.peek(0), Some('s'));
+        mem_reader.consume(1)?;
+        assert_eq!(mem_reader.position(), 2);
+
+        mem_reader.set_position(9);
+        assert_eq!(mem_reader.position(), 4);
+        assert!(mem_reader.has_reached_eof());
+
+        let mut mem_reader = MemoryReader::new("a\u{e9}b".as_bytes())?;
+        mem_reader.set_position(2);
+        assert_eq!(mem_reader.peek(0), Some('b'));
+        assert_eq!(mem_reader.bytes_consumed(), 3);
+        mem_reader.set_position(0);
+        assert_eq!(mem_reader.bytes_consumed(), 0);
+
+        Ok(())
+    }
 }
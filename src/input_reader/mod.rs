@@ -17,6 +17,15 @@
 //! as it will only ever buffer a fixed amount of bytes, at the cost of having
 //! to refill its internal buffer once it has been consumed.
 //!
+//! The [`open_reader`] function follows this guidance, choosing between the
+//! two based on a [`SizeHint`] of the input.
+//!
+//! # `no_std` support
+//!
+//! Without the default `std` feature, the crate only depends on [`alloc`].
+//! [`BufferedReader`] is unavailable then, and [`MemoryReader`] reads from
+//! byte slices through the [`ReadBytes`] trait instead of [`Read`].
+//!
 //! # Input reading in general
 //!
 //! The [`ReadInput`] trait describes a unifying interface for input readers,
@@ -77,16 +86,25 @@
 //! [`peek(k)`]: [`Reader::peek`]
 //! [`consume(k)`]: [`Reader::consume`]
 
-use std::{error, fmt, io, iter, result, str};
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::{fmt, iter, result, str};
+#[cfg(feature = "std")]
+use std::{error, fs, io};
 
+#[cfg(feature = "std")]
 mod buffered_reader;
+mod chain_reader;
+mod limit_reader;
+mod map_reader;
 mod memory_reader;
 
+#[cfg(feature = "std")]
 pub use buffered_reader::BufferedReader;
+pub use chain_reader::ChainReader;
+pub use limit_reader::LimitReader;
+pub use map_reader::MapReader;
 pub use memory_reader::MemoryReader;
 
-use buffered_reader::BUF_READER_CAPACITY;
-
 /// The `ReadInput` trait allows for peeking and consuming input.
 ///
 /// Implementors of the `ReadInput` trait are called 'input readers'.
@@ -97,6 +115,8 @@ use buffered_reader::BUF_READER_CAPACITY;
 /// the current position of the input reader.
 /// Each call to [`consume(k)`] will attempt to advance the input reader's
 /// position by k characters.
+/// Input readers also keep track of the last character they consumed, which
+/// is returned by [`last_consumed`].
 ///
 /// # Examples
 ///
@@ -129,6 +149,7 @@ use buffered_reader::BUF_READER_CAPACITY;
 ///
 /// [`peek(k)`]: [`ReadInput::peek`]
 /// [`consume(k)`]: [`ReadInput::consume`]
+/// [`last_consumed`]: ReadInput::last_consumed
 pub trait ReadInput {
     /// Returns the k-th character in the input from the current position.
     ///
@@ -156,7 +177,7 @@ pub trait ReadInput {
     /// # Errors
     /// This method can fail only when using a [`BufferedReader`], due to
     /// multiple reasons. One of them is trying to consume more characters than
-    /// the internal buffer holds, 16 characters.
+    /// the internal buffer holds, as reported by [`BufferedReader::capacity`].
     /// This method can also fail when trying to refill the buffer. Refilling
     /// the buffer might either yield an [`io::Error`] when trying to read from
     /// the input, or an [`str::Utf8Error`] while trying to convert the
@@ -183,6 +204,45 @@ pub trait ReadInput {
     /// ```
     fn consume(&mut self, k: usize) -> Result<()>;
 
+    /// Returns the most recently consumed character, or `None` if no
+    /// character has been consumed yet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, MemoryReader, ReadInput};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let mut reader = MemoryReader::new("json".as_bytes())?;
+    ///
+    ///     assert_eq!(reader.last_consumed(), None);
+    ///     reader.consume(3)?;
+    ///     assert_eq!(reader.last_consumed(), Some('o'));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn last_consumed(&self) -> Option<char>;
+
+    /// Returns the total number of bytes consumed so far, with characters
+    /// counted by the length of their UTF-8 encoding.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, MemoryReader, ReadInput};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let mut reader = MemoryReader::new("jsön".as_bytes())?;
+    ///
+    ///     reader.consume(3)?;
+    ///     assert_eq!(reader.bytes_consumed(), 4);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn bytes_consumed(&self) -> usize;
+
     /// Checks whether or not the input has ran out of characters.
     ///
     /// # Examples
@@ -206,6 +266,168 @@ pub trait ReadInput {
         matches!(self.peek(0), None)
     }
 
+    /// Consumes characters up to and including the next `'\n'`, or up to the
+    /// end of the input, returning the consumed line without the `'\n'`.
+    ///
+    /// # Errors
+    ///
+    /// This method fails whenever [`consume(k)`] would.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, MemoryReader, ReadInput};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let mut reader = MemoryReader::new("json\nlines".as_bytes())?;
+    ///
+    ///     assert_eq!(reader.consume_line()?, "json");
+    ///     assert_eq!(reader.peek(0), Some('l'));
+    ///     assert_eq!(reader.consume_line()?, "lines");
+    ///     assert!(reader.has_reached_eof());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`consume(k)`]: ReadInput::consume
+    fn consume_line(&mut self) -> Result<String> {
+        let mut line = String::new();
+
+        while let Some(c) = self.peek(0) {
+            self.consume(1)?;
+
+            if c == '\n' {
+                break;
+            }
+            line.push(c);
+        }
+
+        Ok(line)
+    }
+
+    /// Consumes the next character if it is `c`, returning whether it was.
+    /// Nothing is consumed when the next character differs or the input has
+    /// reached its end.
+    ///
+    /// # Errors
+    ///
+    /// This method fails whenever [`consume(k)`] would.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, MemoryReader, ReadInput};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let mut reader = MemoryReader::new("[]".as_bytes())?;
+    ///
+    ///     assert!(reader.expect_char('[')?);
+    ///     assert!(!reader.expect_char(',')?);
+    ///     assert_eq!(reader.peek(0), Some(']'));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`consume(k)`]: ReadInput::consume
+    fn expect_char(&mut self, c: char) -> Result<bool> {
+        if self.peek(0) != Some(c) {
+            return Ok(false);
+        }
+        self.consume(1)?;
+
+        Ok(true)
+    }
+
+    /// Returns how many characters are left to read, or `None` if that isn't
+    /// known up front.
+    ///
+    /// Input readers holding their whole input, such as [`MemoryReader`],
+    /// always know this, while a [`BufferedReader`] only does once it has
+    /// read up to the end of its input. The default implementation returns
+    /// `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, MemoryReader, ReadInput};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let mut reader = MemoryReader::new("json".as_bytes())?;
+    ///     reader.consume(1)?;
+    ///
+    ///     assert_eq!(reader.remaining(), Some(3));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn remaining(&self) -> Option<usize> {
+        None
+    }
+
+    /// Returns the next characters up to, but not including, the first
+    /// occurrence of `delim`, without consuming any of them.
+    ///
+    /// Only the characters that can currently be peeked are searched, so
+    /// `None` is returned when `delim` doesn't occur among them. That is the
+    /// whole remaining input for a [`MemoryReader`], but only the current
+    /// window for a [`BufferedReader`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, MemoryReader, ReadInput};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let reader = MemoryReader::new(r#"key": 1"#.as_bytes())?;
+    ///
+    ///     assert_eq!(reader.peek_until('"'), Some(String::from("key")));
+    ///     assert_eq!(reader.peek_until('}'), None);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn peek_until(&self, delim: char) -> Option<String> {
+        let mut peeked = String::new();
+        for c in (0..).map_while(|k| self.peek(k)) {
+            if c == delim {
+                return Some(peeked);
+            }
+            peeked.push(c);
+        }
+
+        None
+    }
+
+    /// Returns how many of the next characters are JSON whitespace, that is
+    /// `' '`, `'\t'`, `'\n'` or `'\r'`, without consuming any of them.
+    ///
+    /// The run ends at the first character that isn't whitespace, or at the
+    /// last character that can currently be peeked. With the `fast-scan`
+    /// feature enabled, [`MemoryReader`] scans ASCII input several bytes at a
+    /// time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::input_reader::{self, MemoryReader, ReadInput};
+    ///
+    /// fn main() -> input_reader::Result<()> {
+    ///     let reader = MemoryReader::new(" \n\t [] ".as_bytes())?;
+    ///
+    ///     assert_eq!(reader.whitespace_run(), 4);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn whitespace_run(&self) -> usize {
+        (0..)
+            .map_while(|k| self.peek(k))
+            .take_while(|c| matches!(c, ' ' | '\t' | '\n' | '\r'))
+            .count()
+    }
+
     /// Returns an ergonomic iterator over this input reader's input
     /// characters.
     ///
@@ -236,6 +458,221 @@ pub trait ReadInput {
     }
 }
 
+impl<R: ReadInput + ?Sized> ReadInput for &mut R {
+    fn peek(&self, k: usize) -> Option<char> {
+        (**self).peek(k)
+    }
+
+    fn consume(&mut self, k: usize) -> Result<()> {
+        (**self).consume(k)
+    }
+
+    fn last_consumed(&self) -> Option<char> {
+        (**self).last_consumed()
+    }
+
+    fn bytes_consumed(&self) -> usize {
+        (**self).bytes_consumed()
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        (**self).remaining()
+    }
+
+    fn peek_until(&self, delim: char) -> Option<String> {
+        (**self).peek_until(delim)
+    }
+
+    fn whitespace_run(&self) -> usize {
+        (**self).whitespace_run()
+    }
+}
+
+impl<R: ReadInput + ?Sized> ReadInput for Box<R> {
+    fn peek(&self, k: usize) -> Option<char> {
+        (**self).peek(k)
+    }
+
+    fn consume(&mut self, k: usize) -> Result<()> {
+        (**self).consume(k)
+    }
+
+    fn last_consumed(&self) -> Option<char> {
+        (**self).last_consumed()
+    }
+
+    fn bytes_consumed(&self) -> usize {
+        (**self).bytes_consumed()
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        (**self).remaining()
+    }
+
+    fn peek_until(&self, delim: char) -> Option<String> {
+        (**self).peek_until(delim)
+    }
+
+    fn whitespace_run(&self) -> usize {
+        (**self).whitespace_run()
+    }
+}
+
+/// The `ReadBytes` trait describes byte sources a [`MemoryReader`] can read
+/// its whole input from.
+///
+/// With the `std` feature enabled, every [`Read`] implementor is a byte
+/// source. Without it, byte slices are, so that reading input doesn't depend
+/// on [`std::io`].
+///
+/// [`Read`]: std::io::Read
+///
+/// # Examples
+///
+/// ```
+/// use json::input_reader::{self, ReadBytes};
+///
+/// fn main() -> input_reader::Result<()> {
+///     let mut source = "json".as_bytes();
+///     let mut buf = Vec::new();
+///     source.read_all(&mut buf)?;
+///
+///     assert_eq!(buf, b"json");
+///
+///     Ok(())
+/// }
+/// ```
+pub trait ReadBytes {
+    /// Reads all remaining bytes of this byte source, appending them to
+    /// `buf`.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if reading from the underlying source fails.
+    fn read_all(&mut self, buf: &mut Vec<u8>) -> Result<()>;
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read + ?Sized> ReadBytes for R {
+    fn read_all(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        self.read_to_end(buf)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl ReadBytes for &[u8] {
+    fn read_all(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        buf.extend_from_slice(self);
+        *self = &[];
+
+        Ok(())
+    }
+}
+
+/// The size of input above which [`open_reader`] reads it in buffers instead
+/// of whole.
+#[cfg(feature = "std")]
+const MEMORY_READER_LIMIT: u64 = 1 << 20;
+
+/// A hint about the size of an input, in bytes, as used by [`open_reader`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeHint {
+    Bytes(u64),
+    Unknown,
+}
+
+#[cfg(feature = "std")]
+impl SizeHint {
+    /// Returns the length of `file` according to its metadata, or
+    /// [`SizeHint::Unknown`] if the metadata can't be read.
+    pub fn of_file(file: &fs::File) -> Self {
+        file.metadata().map_or(SizeHint::Unknown, |metadata| {
+            SizeHint::Bytes(metadata.len())
+        })
+    }
+}
+
+/// Opens an input reader suited to the size of `source`.
+///
+/// Inputs hinted to be small are read whole by a [`MemoryReader`], while
+/// large inputs and inputs of unknown size are read through a
+/// [`BufferedReader`], keeping memory use bounded.
+///
+/// # Errors
+///
+/// This function fails whenever [`MemoryReader::new`] or
+/// [`BufferedReader::new`] would.
+///
+/// # Examples
+///
+/// ```
+/// use json::input_reader::{self, open_reader, SizeHint};
+///
+/// fn main() -> input_reader::Result<()> {
+///     let source = "json".as_bytes();
+///     let mut reader = open_reader(source, SizeHint::Bytes(source.len() as u64))?;
+///
+///     assert_eq!(reader.peek(0), Some('j'));
+///
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "std")]
+pub fn open_reader<'r, R>(source: R, hint: SizeHint) -> Result<Box<dyn ReadInput + 'r>>
+where
+    R: io::Read + 'r,
+{
+    match hint {
+        SizeHint::Bytes(len) if len <= MEMORY_READER_LIMIT => {
+            Ok(Box::new(MemoryReader::new(source)?))
+        }
+        _ => Ok(Box::new(BufferedReader::new(source)?)),
+    }
+}
+
+/// A progress hook reporting the number of bytes consumed so far every time
+/// another `interval` bytes have been consumed.
+struct Progress {
+    interval: usize,
+    next: usize,
+    bytes: usize,
+    f: Box<dyn FnMut(usize) + Send>,
+}
+
+impl Progress {
+    fn new(interval: usize, f: Box<dyn FnMut(usize) + Send>) -> Self {
+        let interval = interval.max(1);
+
+        Self {
+            interval,
+            next: interval,
+            bytes: 0,
+            f,
+        }
+    }
+
+    fn advance(&mut self, bytes: usize) {
+        self.bytes += bytes;
+
+        if self.bytes >= self.next {
+            (self.f)(self.bytes);
+            self.next = self.bytes - self.bytes % self.interval + self.interval;
+        }
+    }
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Progress")
+            .field("interval", &self.interval)
+            .field("bytes", &self.bytes)
+            .finish()
+    }
+}
+
 /// Iterator over an input reader's input
 ///
 /// This struct is created by the [`input_reader`] method on input readers.
@@ -316,9 +753,13 @@ pub type Result<T> = result::Result<T, Error>;
 /// either [I/O errors] or [UTF-8 errors]. There might also be buffer errors
 /// caused by using a [`BufferedReader`] wrong.
 ///
+/// Since [I/O errors] can't be cloned, a cloned I/O error keeps only the
+/// [`ErrorKind`] and the message of the original.
+///
 /// [I/O errors]: std::io::Error
 /// [UTF-8 errors]: std::str::Utf8Error
-#[derive(Debug)]
+/// [`ErrorKind`]: std::io::ErrorKind
+#[derive(Debug, Clone)]
 pub struct Error {
     #[allow(dead_code)]
     repr: Repr,
@@ -326,24 +767,77 @@ pub struct Error {
 
 #[derive(Debug)]
 enum Repr {
+    #[cfg(feature = "std")]
     Io(io::Error),
+    #[cfg(feature = "std")]
+    Refill(io::Error, usize),
     Utf8(str::Utf8Error),
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
     Buffer(BufferErrorKind),
+    LimitExceeded(usize),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
 enum BufferErrorKind {
-    Overconsumed(usize),
+    Overconsumed { count: usize, capacity: usize },
+}
+
+impl Clone for Repr {
+    fn clone(&self) -> Self {
+        match self {
+            #[cfg(feature = "std")]
+            Repr::Io(io_err) => Repr::Io(clone_io_error(io_err)),
+            #[cfg(feature = "std")]
+            Repr::Refill(io_err, offset) => Repr::Refill(clone_io_error(io_err), *offset),
+            Repr::Utf8(utf8_err) => Repr::Utf8(*utf8_err),
+            Repr::Buffer(buffer_err) => Repr::Buffer(buffer_err.clone()),
+            Repr::LimitExceeded(limit) => Repr::LimitExceeded(*limit),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn clone_io_error(io_err: &io::Error) -> io::Error {
+    io::Error::new(io_err.kind(), io_err.to_string())
 }
 
 impl Error {
-    const fn overconsume_buffer(count: usize) -> Self {
+    #[cfg(feature = "std")]
+    const fn refill_buffer(error: io::Error, offset: usize) -> Self {
+        Self {
+            repr: Repr::Refill(error, offset),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    const fn overconsume_buffer(count: usize, capacity: usize) -> Self {
         Self {
-            repr: Repr::Buffer(BufferErrorKind::Overconsumed(count)),
+            repr: Repr::Buffer(BufferErrorKind::Overconsumed { count, capacity }),
         }
     }
+
+    const fn exceed_limit(limit: usize) -> Self {
+        Self {
+            repr: Repr::LimitExceeded(limit),
+        }
+    }
+
+    /// Returns whether this error was caused by a [`LimitReader`] reaching
+    /// its limit.
+    pub const fn is_limit_exceeded(&self) -> bool {
+        matches!(self.repr, Repr::LimitExceeded(_))
+    }
+
+    pub(crate) const fn is_overconsumed(&self) -> bool {
+        matches!(
+            self.repr,
+            Repr::Buffer(BufferErrorKind::Overconsumed { .. })
+        )
+    }
 }
 
+#[cfg(feature = "std")]
 impl From<io::Error> for Error {
     fn from(error: io::Error) -> Self {
         Self {
@@ -363,30 +857,43 @@ impl From<str::Utf8Error> for Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self.repr {
+            #[cfg(feature = "std")]
             Repr::Io(io_err) => write!(f, "{}", io_err),
+            #[cfg(feature = "std")]
+            Repr::Refill(io_err, offset) => write!(
+                f,
+                "{} (while refilling buffer at offset {})",
+                io_err, offset
+            ),
             Repr::Utf8(utf8_err) => write!(f, "{}", utf8_err),
             Repr::Buffer(buffer_err) => match buffer_err {
-                BufferErrorKind::Overconsumed(count) => write!(
+                BufferErrorKind::Overconsumed { count, capacity } => write!(
                     f,
                     "input reader tried consuming {} characters when its buffer holds only {} characters",
-                    count, BUF_READER_CAPACITY
+                    count, capacity
                 ),
             },
+            Repr::LimitExceeded(limit) => {
+                write!(f, "input exceeds the limit of {} characters", limit)
+            }
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match &self.repr {
             Repr::Io(io_err) => Some(io_err),
+            Repr::Refill(io_err, _offset) => Some(io_err),
             Repr::Utf8(utf8_err) => Some(utf8_err),
             Repr::Buffer(_) => None,
+            Repr::LimitExceeded(_) => None,
         }
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
 
@@ -435,4 +942,211 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_readers_as_trait_objects() -> Result<()> {
+        fn read_all<R: ReadInput>(mut reader: R) -> String {
+            reader.input_reader().collect()
+        }
+
+        let buf_reader: Box<dyn ReadInput> = Box::new(BufferedReader::new(SOURCE)?);
+        let mut mem_reader = MemoryReader::new(SOURCE)?;
+
+        assert_eq!(read_all(buf_reader), "json");
+        assert_eq!(read_all(&mut mem_reader as &mut dyn ReadInput), "json");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_readers_consume_line() -> Result<()> {
+        const LINES: &[u8] = "json\n\nlines\n".as_bytes();
+
+        let mut buf_reader = BufferedReader::new(LINES)?;
+        let mut mem_reader = MemoryReader::new(LINES)?;
+
+        assert_eq!(buf_reader.consume_line()?, "json");
+        assert_eq!(buf_reader.consume_line()?, "");
+        assert_eq!(buf_reader.consume_line()?, "lines");
+        assert!(buf_reader.has_reached_eof());
+
+        assert_eq!(mem_reader.consume_line()?, "json");
+        assert_eq!(mem_reader.consume_line()?, "");
+        assert_eq!(mem_reader.consume_line()?, "lines");
+        assert!(mem_reader.has_reached_eof());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_readers_bytes_consumed() -> Result<()> {
+        const SOURCE: &[u8] = "jsön😀!".as_bytes();
+
+        let mut buf_reader = BufferedReader::new(SOURCE)?;
+        let mut mem_reader = MemoryReader::new(SOURCE)?;
+
+        for reader in [&mut buf_reader as &mut dyn ReadInput, &mut mem_reader] {
+            assert_eq!(reader.bytes_consumed(), 0);
+            reader.consume(2)?;
+            assert_eq!(reader.bytes_consumed(), 2);
+            reader.consume(1)?;
+            assert_eq!(reader.bytes_consumed(), 4);
+            reader.consume(2)?;
+            assert_eq!(reader.bytes_consumed(), 9);
+            reader.consume(2)?;
+            assert_eq!(reader.bytes_consumed(), 10);
+            assert!(reader.has_reached_eof());
+        }
+
+        let mut buf_reader = BufferedReader::with_capacity(2, "abcdéfgh".as_bytes())?;
+        for bytes in [1, 2, 3, 4, 6, 7, 8, 9] {
+            buf_reader.consume(1)?;
+            assert_eq!(buf_reader.bytes_consumed(), bytes);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_reader() -> Result<()> {
+        let source = "[true, false, null, 1]".repeat(4);
+
+        // Only a memory reader can peek past a buffered reader's window.
+        let small = SizeHint::Bytes(source.len() as u64);
+        let reader = open_reader(source.as_bytes(), small)?;
+        assert_eq!(reader.peek(buffered_reader::BUF_READER_CAPACITY), Some('l'));
+
+        let large = SizeHint::Bytes(MEMORY_READER_LIMIT + 1);
+        let reader = open_reader(source.as_bytes(), large)?;
+        assert_eq!(reader.peek(buffered_reader::BUF_READER_CAPACITY), None);
+        assert_eq!(reader.peek(1), Some('t'));
+
+        let reader = open_reader(source.as_bytes(), SizeHint::Unknown)?;
+        assert_eq!(reader.peek(buffered_reader::BUF_READER_CAPACITY), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_readers_expect_char() -> Result<()> {
+        const SOURCE: &[u8] = "{:}".as_bytes();
+
+        let mut buf_reader = BufferedReader::new(SOURCE)?;
+        let mut mem_reader = MemoryReader::new(SOURCE)?;
+
+        for reader in [&mut buf_reader as &mut dyn ReadInput, &mut mem_reader] {
+            assert!(!reader.expect_char(':')?);
+            assert_eq!(reader.peek(0), Some('{'));
+            assert!(reader.expect_char('{')?);
+            assert!(reader.expect_char(':')?);
+            assert_eq!(reader.last_consumed(), Some(':'));
+            assert!(reader.expect_char('}')?);
+            assert!(!reader.expect_char('}')?);
+            assert!(reader.has_reached_eof());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_readers_peek_until() -> Result<()> {
+        const SOURCE: &[u8] = r#""a\"b", "c""#.as_bytes();
+
+        let buf_reader = BufferedReader::new(SOURCE)?;
+        let mem_reader = MemoryReader::new(SOURCE)?;
+
+        for reader in [&buf_reader as &dyn ReadInput, &mem_reader] {
+            assert_eq!(reader.peek_until('"'), Some(String::new()));
+            assert_eq!(reader.peek_until(','), Some(String::from(r#""a\"b""#)));
+            assert_eq!(reader.peek_until('c'), Some(String::from(r#""a\"b", ""#)));
+            assert_eq!(reader.peek_until('}'), None);
+            assert_eq!(reader.peek(0), Some('"'));
+        }
+
+        // The delimiter lies past the window of a small buffer.
+        let buf_reader = BufferedReader::with_capacity(4, SOURCE)?;
+        assert_eq!(buf_reader.peek_until('\\'), Some(String::from(r#""a"#)));
+        assert_eq!(buf_reader.peek_until(','), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_readers_consume_line_without_newline() -> Result<()> {
+        let mut buf_reader = BufferedReader::new(SOURCE)?;
+        let mut mem_reader = MemoryReader::new(SOURCE)?;
+
+        assert_eq!(buf_reader.consume_line()?, "json");
+        assert_eq!(mem_reader.consume_line()?, "json");
+
+        assert!(buf_reader.has_reached_eof());
+        assert!(mem_reader.has_reached_eof());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clone_error() {
+        let err = Error::from(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "input ended early",
+        ));
+        let cloned = err.clone();
+
+        assert_eq!(cloned.to_string(), err.to_string());
+        let source = error::Error::source(&cloned).and_then(|source| source.downcast_ref());
+        assert_eq!(
+            source.map(io::Error::kind),
+            Some(io::ErrorKind::UnexpectedEof)
+        );
+
+        let err = MemoryReader::new(&b"j\xffon"[..]).unwrap_err();
+        assert_eq!(err.clone().to_string(), err.to_string());
+    }
+
+    #[test]
+    fn test_readers_are_send() {
+        fn assert_send<T: Send>() {}
+
+        assert_send::<MemoryReader>();
+        assert_send::<BufferedReader<&[u8]>>();
+        assert_send::<ChainReader<MemoryReader>>();
+        assert_send::<LimitReader<MemoryReader>>();
+    }
+
+    #[test]
+    fn test_readers_progress() -> Result<()> {
+        use std::sync::{Arc, Mutex};
+
+        fn progress_of<R: ReadInput>(
+            mut reader: R,
+            on_progress: impl FnOnce(&mut R, Box<dyn FnMut(usize) + Send>),
+        ) -> Result<Vec<usize>> {
+            let calls = Arc::new(Mutex::new(Vec::new()));
+            let recorder = Arc::clone(&calls);
+            on_progress(
+                &mut reader,
+                Box::new(move |bytes| recorder.lock().unwrap().push(bytes)),
+            );
+
+            while !reader.has_reached_eof() {
+                reader.consume(3)?;
+            }
+
+            let calls = calls.lock().unwrap().clone();
+            Ok(calls)
+        }
+
+        let source = "jsön".repeat(8);
+        let mem_reader = MemoryReader::new(source.as_bytes())?;
+        let buf_reader = BufferedReader::new(source.as_bytes())?;
+
+        let mem_calls = progress_of(mem_reader, |reader, f| reader.on_progress(8, f))?;
+        let buf_calls = progress_of(buf_reader, |reader, f| reader.on_progress(8, f))?;
+
+        assert_eq!(mem_calls, [11, 19, 26, 34, 40]);
+        assert_eq!(buf_calls, mem_calls);
+
+        Ok(())
+    }
 }
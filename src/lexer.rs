@@ -1,49 +1,116 @@
-use std::{borrow::Cow, error, fmt, result};
+use alloc::{
+    borrow::Cow,
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, mem, result};
+#[cfg(feature = "std")]
+use std::error;
 
 use crate::input_reader;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Error {
     #[allow(dead_code)]
     repr: Repr,
+    pos: Option<Pos>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum Repr {
     InputReader(input_reader::Error),
+    TokenTooLong(input_reader::Error),
+    NumberTooLong(usize),
+    StringTooLong(usize),
+    DetachedExponent,
     Expected(ExpectedKind),
     Unexpected(char),
 }
 
-#[derive(Debug)]
-enum ExpectedKind {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    InputReader,
+    TokenTooLong,
+    NumberTooLong,
+    StringTooLong,
+    DetachedExponent,
+    Expected(ExpectedKind),
+    Unexpected(char),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExpectedKind {
     Keyword(&'static str),
     Digit(DigitKind),
     StrTerminator,
     EscapedChar,
 }
 
-#[derive(Debug)]
-enum DigitKind {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigitKind {
     Dec,
     Hex,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexCase {
+    Lower,
+    Upper,
+}
+
 use DigitKind::{Dec, Hex};
 use ExpectedKind::{Digit, EscapedChar, Keyword, StrTerminator};
-use Repr::{Expected, InputReader, Unexpected};
+use Repr::{
+    DetachedExponent, Expected, InputReader, NumberTooLong, StringTooLong, TokenTooLong, Unexpected,
+};
 
 impl From<input_reader::Error> for Error {
     fn from(error: input_reader::Error) -> Self {
-        Self {
-            repr: Repr::InputReader(error),
-        }
+        let repr = if error.is_overconsumed() {
+            TokenTooLong(error)
+        } else {
+            InputReader(error)
+        };
+
+        Self { repr, pos: None }
     }
 }
 
 impl From<Repr> for Error {
     fn from(repr: Repr) -> Self {
-        Self { repr }
+        Self { repr, pos: None }
+    }
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match &self.repr {
+            InputReader(_input_reader_err) => ErrorKind::InputReader,
+            TokenTooLong(_input_reader_err) => ErrorKind::TokenTooLong,
+            NumberTooLong(_max_len) => ErrorKind::NumberTooLong,
+            StringTooLong(_max_len) => ErrorKind::StringTooLong,
+            DetachedExponent => ErrorKind::DetachedExponent,
+            Expected(expected_kind) => ErrorKind::Expected(*expected_kind),
+            Unexpected(unexpected_char) => ErrorKind::Unexpected(*unexpected_char),
+        }
+    }
+
+    fn at(self, pos: Pos) -> Self {
+        Self {
+            repr: self.repr,
+            pos: Some(pos),
+        }
+    }
+
+    pub fn render(&self, source: &str) -> String {
+        match self.pos {
+            Some(pos) => pos.render(self, source),
+            None => format!("error: {}", self),
+        }
     }
 }
 
@@ -51,6 +118,27 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self.repr {
             InputReader(input_reader_err) => write!(f, "{}", input_reader_err),
+            TokenTooLong(_input_reader_err) => {
+                write!(f, "token is too long for the input reader's buffer")
+            }
+            NumberTooLong(max_len) => {
+                write!(
+                    f,
+                    "number literal exceeds the limit of {} characters",
+                    max_len
+                )
+            }
+            StringTooLong(max_len) => {
+                write!(
+                    f,
+                    "string literal exceeds the limit of {} characters",
+                    max_len
+                )
+            }
+            DetachedExponent => write!(
+                f,
+                "exponent must immediately follow the number, no whitespace allowed"
+            ),
             Expected(expected_kind) => match expected_kind {
                 Keyword(kw) => write!(f, "expected keyword \"{}\"", kw),
                 Digit(kind) => match kind {
@@ -60,15 +148,25 @@ impl fmt::Display for Error {
                 StrTerminator => write!(f, "expected string terminator '\"'"),
                 EscapedChar => write!(f, "expected escaped character"),
             },
+            Unexpected(unexpected_char) if is_invisible(*unexpected_char) => write!(
+                f,
+                "unexpected character U+{:04X}",
+                u32::from(*unexpected_char)
+            ),
             Unexpected(unexpected_char) => write!(f, "unexpected character '{}'", unexpected_char),
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match &self.repr {
             InputReader(input_reader_err) => Some(input_reader_err),
+            TokenTooLong(input_reader_err) => Some(input_reader_err),
+            NumberTooLong(_max_len) => None,
+            StringTooLong(_max_len) => None,
+            DetachedExponent => None,
             Expected(_expected_kind) => None,
             Unexpected(_unexpected_char) => None,
         }
@@ -77,14 +175,164 @@ impl error::Error for Error {
 
 pub type Result<T> = result::Result<T, Error>;
 
+fn decode_escape(escape: &str) -> Option<char> {
+    let mut chars = escape.chars().skip(1);
+
+    let decoded = match chars.next()? {
+        'b' => '\u{8}',
+        'f' => '\u{c}',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'u' | 'x' => u32::from_str_radix(&escape[2..], 16)
+            .ok()
+            .and_then(char::from_u32)
+            .unwrap_or(char::REPLACEMENT_CHARACTER),
+        c => c,
+    };
+
+    Some(decoded)
+}
+
+fn is_invisible(c: char) -> bool {
+    c.is_control()
+        || (c.is_whitespace() && c != ' ')
+        || matches!(
+            c,
+            '\u{ad}'
+                | '\u{200b}'..='\u{200f}'
+                | '\u{202a}'..='\u{202e}'
+                | '\u{2060}'..='\u{2064}'
+                | '\u{2066}'..='\u{2069}'
+                | BYTE_ORDER_MARK
+        )
+}
+
+// Rewrites the hex digits of `\u` and `\x` escapes in an already
+// validated string body, or returns `None` when they are already in `case`.
+fn normalize_hex_case(raw: &str, case: HexCase) -> Option<String> {
+    let mut normalized = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    let mut changed = false;
+
+    while let Some(c) = chars.next() {
+        normalized.push(c);
+        if c != '\\' {
+            continue;
+        }
+
+        let escaped = match chars.next() {
+            Some(escaped) => escaped,
+            None => break,
+        };
+        normalized.push(escaped);
+        let count = match escaped {
+            'u' => 4,
+            'x' => 2,
+            _ => continue,
+        };
+
+        for digit in chars.by_ref().take(count) {
+            let cased = match case {
+                HexCase::Lower => digit.to_ascii_lowercase(),
+                HexCase::Upper => digit.to_ascii_uppercase(),
+            };
+            changed |= cased != digit;
+            normalized.push(cased);
+        }
+    }
+
+    changed.then_some(normalized)
+}
+
+const BYTE_ORDER_MARK: char = '\u{feff}';
+
 #[derive(Debug)]
 pub struct Lexer<R> {
     input_reader: R,
     current_token: Option<Token>,
+    options: LexerOptions,
+    started: bool,
+    tokens: usize,
+    // Reused across literals so that only the final, exactly sized copy
+    // handed out in the token is allocated per literal, and nothing at all
+    // when only spans are wanted.
+    scratch: String,
+    spans_only: bool,
 
     pos: Pos,
 }
 
+pub struct LexerOptions {
+    pub json5: bool,
+    pub coalesce_whitespace: bool,
+    pub allow_leading_decimal_point: bool,
+    pub max_number_len: usize,
+    pub max_string_len: usize,
+    pub escape_spans: bool,
+    pub tab_width: usize,
+    pub allow_control_chars: bool,
+    /// Rewrites the hex digits of `\u` and `\x` escapes in the normalized
+    /// form of string tokens, leaving `raw_source` as written. `None` keeps
+    /// the source casing.
+    pub hex_case: Option<HexCase>,
+    /// Recognizes literals of a custom dialect, such as `undefined`.
+    ///
+    /// The hook is consulted when a character starts no standard token. It
+    /// is passed that character, already consumed, and the input reader
+    /// positioned right after it. Returning `Some((kind, raw))` emits a
+    /// token of that kind, while returning `None` reports the character as
+    /// unexpected.
+    ///
+    /// The lexer advances its position by the characters of `raw`, so the
+    /// hook is responsible for consuming exactly the characters of `raw`
+    /// after the first one, none of them line breaks, and for consuming
+    /// nothing when returning `None`.
+    pub custom_literal: Option<Box<LiteralHook>>,
+}
+
+pub type LiteralHook =
+    dyn Fn(char, &mut dyn input_reader::ReadInput) -> LiteralResult + Send + Sync;
+
+pub type LiteralResult = input_reader::Result<Option<(TokenKind, String)>>;
+
+impl Default for LexerOptions {
+    fn default() -> Self {
+        Self {
+            json5: false,
+            coalesce_whitespace: false,
+            allow_leading_decimal_point: false,
+            max_number_len: 1024,
+            max_string_len: usize::MAX,
+            escape_spans: false,
+            tab_width: 1,
+            allow_control_chars: false,
+            hex_case: None,
+            custom_literal: None,
+        }
+    }
+}
+
+impl fmt::Debug for LexerOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LexerOptions")
+            .field("json5", &self.json5)
+            .field("coalesce_whitespace", &self.coalesce_whitespace)
+            .field(
+                "allow_leading_decimal_point",
+                &self.allow_leading_decimal_point,
+            )
+            .field("max_number_len", &self.max_number_len)
+            .field("max_string_len", &self.max_string_len)
+            .field("escape_spans", &self.escape_spans)
+            .field("tab_width", &self.tab_width)
+            .field("allow_control_chars", &self.allow_control_chars)
+            .field("hex_case", &self.hex_case)
+            .field("custom_literal", &self.custom_literal.is_some())
+            .finish()
+    }
+}
+
 #[derive(Debug)]
 pub struct IntoIter<R> {
     lexer: Lexer<R>,
@@ -93,22 +341,36 @@ pub struct IntoIter<R> {
 
 #[derive(Debug)]
 pub struct Token {
-    #[allow(dead_code)]
     kind: TokenKind,
     raw: Cow<'static, str>,
+    source: Option<String>,
+    escapes: Vec<((Pos, Pos), char)>,
     start: Pos,
     end: Pos,
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Pos {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pos {
     line: usize,
     column: usize,
     offset: usize,
+    tab_width: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LexStats {
+    pub tokens: usize,
+    pub bytes: usize,
+    pub lines: usize,
 }
 
 #[derive(Debug)]
-enum TokenKind {
+pub struct SpannedTokens<R> {
+    inner: IntoIter<R>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
     Whitespace,
 
     Comma,
@@ -118,20 +380,21 @@ enum TokenKind {
     CloseBracket,
     Colon,
 
+    Identifier,
     Literal { kind: LiteralKind },
 }
 
-#[derive(Debug)]
-enum LiteralKind {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiteralKind {
     Null,
     Bool,
     Num,
     Str,
 }
 
-use LiteralKind::{Bool, Null, Num, Str};
-use TokenKind::{
-    CloseBrace, CloseBracket, Colon, Comma, Literal, OpenBrace, OpenBracket, Whitespace,
+pub(crate) use LiteralKind::{Bool, Null, Num, Str};
+pub(crate) use TokenKind::{
+    CloseBrace, CloseBracket, Colon, Comma, Identifier, Literal, OpenBrace, OpenBracket, Whitespace,
 };
 
 impl From<(TokenKind, String, Pos, Pos)> for Token {
@@ -139,6 +402,8 @@ impl From<(TokenKind, String, Pos, Pos)> for Token {
         Token {
             kind,
             raw: Cow::from(raw),
+            source: None,
+            escapes: Vec::new(),
             start,
             end,
         }
@@ -150,6 +415,8 @@ impl From<(TokenKind, &'static str, Pos, Pos)> for Token {
         Token {
             kind,
             raw: Cow::from(raw),
+            source: None,
+            escapes: Vec::new(),
             start,
             end,
         }
@@ -158,20 +425,171 @@ impl From<(TokenKind, &'static str, Pos, Pos)> for Token {
 
 impl From<(TokenKind, char, Pos, Pos)> for Token {
     fn from((kind, raw, start, end): (TokenKind, char, Pos, Pos)) -> Self {
+        // Punctuation and whitespace make up most tokens, so borrowing their
+        // raw text saves an allocation per token.
+        let raw = match raw {
+            ',' => Cow::from(","),
+            '{' => Cow::from("{"),
+            '}' => Cow::from("}"),
+            '[' => Cow::from("["),
+            ']' => Cow::from("]"),
+            ':' => Cow::from(":"),
+            ' ' => Cow::from(" "),
+            '\t' => Cow::from("\t"),
+            '\n' => Cow::from("\n"),
+            '\r' => Cow::from("\r"),
+            _ => Cow::from(String::from(raw)),
+        };
+
         Token {
             kind,
-            raw: Cow::from(String::from(raw)),
+            raw,
+            source: None,
+            escapes: Vec::new(),
             start,
             end,
         }
     }
 }
 
+impl Pos {
+    pub const fn line(&self) -> usize {
+        self.line
+    }
+
+    pub const fn column(&self) -> usize {
+        self.column
+    }
+
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub(crate) fn render(&self, message: &dyn fmt::Display, source: &str) -> String {
+        let line = source.lines().nth(self.line - 1).unwrap_or_default();
+        let gutter = self.line.to_string();
+        let padding = " ".repeat(gutter.len());
+        // Columns count tabs as `tab_width` wide, so the caret is indented by
+        // display width rather than by characters.
+        let mut width = 0;
+        let indent = line
+            .chars()
+            .take_while(|&c| {
+                let fits = width < self.column - 1;
+                width += if c == '\t' { self.tab_width } else { 1 };
+                fits
+            })
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+
+        format!(
+            "error: {}\n{}--> {}:{}\n{} |\n{} | {}\n{} | {}^",
+            message, padding, self.line, self.column, padding, gutter, line, padding, indent
+        )
+    }
+}
+
+impl Token {
+    pub const fn kind(&self) -> TokenKind {
+        self.kind
+    }
+
+    pub const fn start(&self) -> Pos {
+        self.start
+    }
+
+    pub const fn end(&self) -> Pos {
+        self.end
+    }
+
+    pub const fn is_structural(&self) -> bool {
+        matches!(
+            self.kind,
+            Comma | OpenBrace | CloseBrace | OpenBracket | CloseBracket | Colon
+        )
+    }
+
+    pub const fn is_value(&self) -> bool {
+        matches!(self.kind, Literal { .. })
+    }
+
+    pub const fn is_whitespace(&self) -> bool {
+        matches!(self.kind, Whitespace)
+    }
+
+    pub fn raw_source(&self) -> &str {
+        self.source.as_deref().unwrap_or(&self.raw)
+    }
+
+    pub fn normalized(&self) -> &str {
+        &self.raw
+    }
+
+    pub fn escapes(&self) -> &[((Pos, Pos), char)] {
+        &self.escapes
+    }
+
+    fn number(source: String, start: Pos, end: Pos) -> Self {
+        let kind = Literal { kind: Num };
+        if !source.starts_with('+') && !source.contains('_') {
+            return Token::from((kind, source, start, end));
+        }
+
+        let normalized = source
+            .trim_start_matches('+')
+            .chars()
+            .filter(|&c| c != '_')
+            .collect::<String>();
+        let mut token = Token::from((kind, normalized, start, end));
+        token.source = Some(source);
+
+        token
+    }
+
+    pub(crate) fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    pub const fn line(&self) -> usize {
+        self.start.line
+    }
+
+    pub const fn column(&self) -> usize {
+        self.start.column
+    }
+}
+
 impl<R> Lexer<R> {
+    /// Returns the current token, or `None` at the end of input. A lexer
+    /// created lazily has no current token until its first `consume` either,
+    /// which `is_started` tells apart from the end of input.
     pub const fn peek(&self) -> Option<&Token> {
         self.current_token.as_ref()
     }
 
+    pub const fn is_started(&self) -> bool {
+        self.started
+    }
+
+    pub const fn position(&self) -> Pos {
+        self.pos
+    }
+
+    pub const fn stats(&self) -> LexStats {
+        LexStats {
+            tokens: self.tokens,
+            bytes: self.pos.offset,
+            lines: self.pos.line,
+        }
+    }
+
+    pub const fn spanned_tokens(mut self) -> SpannedTokens<R> {
+        self.spans_only = true;
+        SpannedTokens {
+            inner: self.into_iter(),
+        }
+    }
+
     const fn into_iter(self) -> IntoIter<R> {
         IntoIter {
             lexer: self,
@@ -182,21 +600,56 @@ impl<R> Lexer<R> {
 
 impl<R: input_reader::ReadInput> Lexer<R> {
     pub fn new(input_reader: R) -> Result<Self> {
-        let mut lexer = Self {
+        Self::with_options(input_reader, LexerOptions::default())
+    }
+
+    pub fn with_options(input_reader: R, options: LexerOptions) -> Result<Self> {
+        let mut lexer = Self::lazy_with_options(input_reader, options);
+        lexer.consume()?;
+
+        Ok(lexer)
+    }
+
+    /// Creates a lexer without reading any input yet. Unlike with `new`, no
+    /// token is peekable until the first `consume`, which is also when
+    /// errors from the start of the input surface, so `peek` returns `None`
+    /// until then as if the input were empty. Iterating consumes first.
+    pub fn new_lazy(input_reader: R) -> Self {
+        Self::lazy_with_options(input_reader, LexerOptions::default())
+    }
+
+    pub fn collect_tokens(self) -> Result<Vec<Token>> {
+        self.into_iter().collect()
+    }
+
+    pub(crate) fn has_reached_eof(&self) -> bool {
+        self.input_reader.has_reached_eof()
+    }
+
+    /// Creates a lexer like `new_lazy` does, but with the given options.
+    pub fn lazy_with_options(input_reader: R, options: LexerOptions) -> Self {
+        let tab_width = options.tab_width;
+
+        Self {
             input_reader,
             current_token: None,
+            options,
+            started: false,
+            tokens: 0,
+            scratch: String::new(),
+            spans_only: false,
 
             pos: Pos {
                 column: 1,
                 line: 1,
                 offset: 0,
+                tab_width,
             },
-        };
-        lexer.consume()?;
-
-        Ok(lexer)
+        }
     }
 
+    // Offsets are byte indices into the source, so this is only correct for
+    // ASCII characters. Anything else goes through `advance_text`.
     fn advance_column(&mut self, by: usize) -> Pos {
         self.pos.column += by;
         self.pos.offset += by;
@@ -204,6 +657,20 @@ impl<R: input_reader::ReadInput> Lexer<R> {
         self.pos
     }
 
+    fn advance_text(&mut self, text: &str) -> Pos {
+        self.pos.column += text.chars().count();
+        self.pos.offset += text.len();
+
+        self.pos
+    }
+
+    fn advance_tab(&mut self) -> Pos {
+        self.pos.column += self.options.tab_width;
+        self.pos.offset += 1;
+
+        self.pos
+    }
+
     fn advance_line(&mut self) -> Pos {
         self.pos.column = 1;
         self.pos.line += 1;
@@ -215,19 +682,52 @@ impl<R: input_reader::ReadInput> Lexer<R> {
     pub fn consume(&mut self) -> Result<()> {
         self.current_token = None;
 
+        if !self.started {
+            self.started = true;
+
+            // A leading byte order mark isn't part of the document, so it is
+            // skipped without advancing the column, only the offset.
+            if self.input_reader.peek(0) == Some(BYTE_ORDER_MARK) {
+                self.input_reader.consume(1)?;
+                self.pos.offset += BYTE_ORDER_MARK.len_utf8();
+            }
+        }
+
         let start = self.pos;
+        self.current_token = self.match_token(start).map_err(|err| err.at(start))?;
+        if self.current_token.is_some() {
+            self.tokens += 1;
+        }
+
+        Ok(())
+    }
 
+    fn match_token(&mut self, start: Pos) -> Result<Option<Token>> {
         if let Some(c) = self.advance_input_reader()? {
             let token = match c {
-                ' ' | '\t' => Token::from((Whitespace, c, start, self.advance_column(1))),
+                ' ' | '\t' | '\n' | '\r' if self.options.coalesce_whitespace => {
+                    let raw = self.match_whitespace(c)?;
+                    Token::from((Whitespace, raw, start, self.pos))
+                }
+                ' ' => Token::from((Whitespace, c, start, self.advance_column(1))),
+                '\t' => Token::from((Whitespace, c, start, self.advance_tab())),
                 '\n' => Token::from((Whitespace, c, start, self.advance_line())),
                 '\r' => Token::from((Whitespace, c, start, self.advance_column(1))),
+                // ECMAScript line terminators, taking three bytes in UTF-8.
+                '\u{2028}' | '\u{2029}' if self.options.json5 => {
+                    self.pos.offset += c.len_utf8() - 1;
+                    Token::from((Whitespace, c, start, self.advance_line()))
+                }
                 ',' => Token::from((Comma, c, start, self.advance_column(1))),
                 '{' => Token::from((OpenBrace, c, start, self.advance_column(1))),
                 '}' => Token::from((CloseBrace, c, start, self.advance_column(1))),
                 '[' => Token::from((OpenBracket, c, start, self.advance_column(1))),
                 ']' => Token::from((CloseBracket, c, start, self.advance_column(1))),
                 ':' => Token::from((Colon, c, start, self.advance_column(1))),
+                'a'..='z' | 'A'..='Z' | '_' | '$' if self.options.json5 => {
+                    let (kind, raw) = self.match_identifier(c)?;
+                    Token::from((kind, raw, start, self.pos))
+                }
                 'n' => Token::from((
                     Literal { kind: Null },
                     self.match_keyword("null")?,
@@ -246,23 +746,57 @@ impl<R: input_reader::ReadInput> Lexer<R> {
                     start,
                     self.advance_column(5),
                 )),
-                '0'..='9' | '-' => {
-                    let raw = self.match_number(c)?;
-                    let len = raw.len();
-                    Token::from((Literal { kind: Num }, raw, start, self.advance_column(len)))
+                '0'..='9' | '-' | '+' | '.'
+                    if match c {
+                        '.' => self.options.allow_leading_decimal_point,
+                        '+' => self.options.json5,
+                        _ => true,
+                    } =>
+                {
+                    let (source, len) = self.match_number(c)?;
+                    Token::number(source, start, self.advance_column(len))
                 }
                 '"' => {
-                    let raw = self.match_string()?;
-                    let len = raw.len() + 2;
-                    Token::from((Literal { kind: Str }, raw, start, self.advance_column(len)))
+                    self.advance_column(1);
+                    let mut escapes = Vec::new();
+                    let raw = self.match_string(&mut escapes)?;
+                    let kind = Literal { kind: Str };
+                    let normalized = self
+                        .options
+                        .hex_case
+                        .and_then(|case| normalize_hex_case(&raw, case));
+                    let mut token = match normalized {
+                        Some(normalized) => {
+                            let mut token = Token::from((kind, normalized, start, self.pos));
+                            token.source = Some(raw);
+                            token
+                        }
+                        None => Token::from((kind, raw, start, self.pos)),
+                    };
+                    token.escapes = escapes;
+
+                    token
                 }
-                _ => return Err(Error::from(Unexpected(c))),
+                _ => match self.match_custom_literal(c)? {
+                    Some((kind, raw)) => {
+                        let end = self.advance_text(&raw);
+                        Token::from((kind, raw, start, end))
+                    }
+                    None => return Err(Error::from(Unexpected(c))),
+                },
             };
 
-            self.current_token = Some(token);
+            return Ok(Some(token));
         }
 
-        Ok(())
+        Ok(None)
+    }
+
+    fn match_custom_literal(&mut self, first: char) -> Result<Option<(TokenKind, String)>> {
+        match &self.options.custom_literal {
+            Some(hook) => Ok(hook(first, &mut self.input_reader)?),
+            None => Ok(None),
+        }
     }
 
     fn advance_input_reader(&mut self) -> Result<Option<char>> {
@@ -275,41 +809,113 @@ impl<R: input_reader::ReadInput> Lexer<R> {
         Ok(None)
     }
 
+    fn match_whitespace(&mut self, first: char) -> Result<String> {
+        let mut whitespace = self.take_scratch();
+        whitespace.push(first);
+
+        loop {
+            // Consume as much of the run as can be peeked at once rather than
+            // a character at a time.
+            let run = self.input_reader.whitespace_run();
+            if run == 0 {
+                break;
+            }
+
+            whitespace.extend((0..run).filter_map(|k| self.input_reader.peek(k)));
+            self.input_reader.consume(run)?;
+        }
+
+        for c in whitespace.chars() {
+            match c {
+                '\n' => self.advance_line(),
+                '\t' => self.advance_tab(),
+                _ => self.advance_column(1),
+            };
+        }
+
+        Ok(self.finish_raw(whitespace))
+    }
+
+    fn match_identifier(&mut self, first: char) -> Result<(TokenKind, String)> {
+        let mut identifier = self.take_scratch();
+        identifier.push(first);
+        self.advance_column(1);
+
+        while let Some(c @ ('a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '$')) =
+            self.input_reader.peek(0)
+        {
+            identifier.push(c);
+            self.input_reader.consume(1)?;
+            self.advance_column(1);
+        }
+
+        let kind = match identifier.as_str() {
+            "null" => Literal { kind: Null },
+            "true" | "false" => Literal { kind: Bool },
+            _ => Identifier,
+        };
+
+        Ok((kind, self.finish_raw(identifier)))
+    }
+
     fn match_keyword(&mut self, kw: &'static str) -> Result<&'static str> {
-        let actual = (0..kw.len() - 1).filter_map(|k| self.input_reader.peek(k));
+        let rest = kw.len() - 1;
+        let matched = (0..rest)
+            .map_while(|k| self.input_reader.peek(k))
+            .zip(kw.chars().skip(1))
+            .take_while(|(actual, expected)| actual == expected)
+            .count();
+
+        if matched < rest {
+            // Running out of characters midway through the keyword may only
+            // mean it doesn't fit the input reader's buffer, in which case
+            // consuming all of it reports that instead.
+            if self.input_reader.peek(matched).is_none() {
+                self.input_reader.consume(rest)?;
+            }
 
-        if actual.ne(kw.chars().skip(1)) {
             return Err(Error::from(Expected(Keyword(kw))));
         }
 
-        self.input_reader.consume(kw.len() - 1)?;
+        self.input_reader.consume(rest)?;
 
         Ok(kw)
     }
 
-    fn consume_digits(&mut self) -> Result<String> {
-        let mut digits = String::new();
+    fn consume_digits(&mut self, literal: &mut String) -> Result<usize> {
+        let mut count = 0;
 
         loop {
+            // Separators count towards the limit too, so that no run of them,
+            // in the exponent or elsewhere, grows the literal unbounded.
+            if matches!(self.input_reader.peek(0), Some('_' | '0'..='9'))
+                && literal.len() >= self.options.max_number_len
+            {
+                return Err(Error::from(NumberTooLong(self.options.max_number_len)));
+            }
+
             match self.input_reader.peek(0) {
                 Some('_') => {
+                    literal.push('_');
                     self.input_reader.consume(1)?;
                 }
                 Some(c @ '0'..='9') => {
-                    digits.push(c);
+                    literal.push(c);
+                    count += 1;
                     self.input_reader.consume(1)?;
                 }
                 _ => break,
             }
         }
 
-        Ok(digits)
+        Ok(count)
     }
 
-    fn match_number(&mut self, first_digit: char) -> Result<String> {
-        let mut literal = String::from(first_digit);
+    fn match_number(&mut self, first_digit: char) -> Result<(String, usize)> {
+        let mut literal = self.take_scratch();
+        literal.push(first_digit);
 
-        let first_digit = if first_digit == '-' {
+        let first_digit = if matches!(first_digit, '-' | '+') {
             let c = self
                 .advance_input_reader()?
                 .ok_or_else(|| Error::from(Expected(Digit(Dec))))?;
@@ -320,22 +926,28 @@ impl<R: input_reader::ReadInput> Lexer<R> {
             first_digit
         };
 
+        let allow_decimal_point = self.options.allow_leading_decimal_point;
         match first_digit {
-            '1'..='9' => literal.push_str(&self.consume_digits()?),
+            '1'..='9' => {
+                self.consume_digits(&mut literal)?;
+            }
             '0' => {}
+            '.' if allow_decimal_point => {
+                if self.consume_digits(&mut literal)? == 0 {
+                    return Err(Error::from(Expected(Digit(Dec))));
+                }
+            }
             _ => return Err(Error::from(Expected(Digit(Dec)))),
         }
 
-        if matches!(self.input_reader.peek(0), Some(c) if c == '.') {
+        if first_digit != '.' && self.input_reader.peek(0) == Some('.') {
             self.advance_input_reader().unwrap();
             literal.push('.');
 
-            let fractional = self.consume_digits()?;
-            if fractional.is_empty() {
+            let fractional = self.consume_digits(&mut literal)?;
+            if fractional == 0 && !allow_decimal_point {
                 return Err(Error::from(Expected(Digit(Dec))));
             }
-
-            literal.push_str(&fractional);
         }
 
         if matches!(self.input_reader.peek(0), Some('e' | 'E')) {
@@ -348,54 +960,140 @@ impl<R: input_reader::ReadInput> Lexer<R> {
                 literal.push(c);
             }
 
-            let exponent = self.consume_digits()?;
-            if exponent.is_empty() {
+            if self.consume_digits(&mut literal)? == 0 {
                 return Err(Error::from(Expected(Digit(Dec))));
             }
+        }
 
-            literal.push_str(&exponent);
+        // An exponent separated from its number, as in `1 e5`, can't start
+        // any valid token, so it gets a clearer message than a stray `e`.
+        let run = self.input_reader.whitespace_run();
+        if run > 0
+            && matches!(self.input_reader.peek(run), Some('e' | 'E'))
+            && matches!(self.input_reader.peek(run + 1), Some('0'..='9' | '-' | '+'))
+        {
+            return Err(Error::from(DetachedExponent));
         }
 
-        Ok(literal)
+        let len = literal.len();
+
+        Ok((self.finish_raw(literal), len))
     }
 
-    fn match_string(&mut self) -> Result<String> {
-        let mut codepoints = String::new();
+    fn match_string(&mut self, escapes: &mut Vec<((Pos, Pos), char)>) -> Result<String> {
+        let mut codepoints = self.take_scratch();
+        let (mut len, mut counted) = (0, 0);
 
         loop {
+            // Escape sequences count as written in the source, and counting
+            // only what was pushed since the last check keeps this linear.
+            len += codepoints[counted..].chars().count();
+            counted = codepoints.len();
+            if len > self.options.max_string_len {
+                return Err(Error::from(StringTooLong(self.options.max_string_len)));
+            }
+
             match self.advance_input_reader()? {
-                Some(c) if c == '"' => break,
-                Some(c) if c.is_ascii_control() => return Err(Error::from(Unexpected(c))),
-                Some(c) if c == '\\' => {
-                    codepoints.push(c);
+                Some('"') => {
+                    self.advance_column(1);
+                    break;
+                }
+                // Line breaks stay rejected so that strings never span lines.
+                Some(c)
+                    if c.is_ascii_control()
+                        && (!self.options.allow_control_chars || matches!(c, '\n' | '\r')) =>
+                {
+                    return Err(Error::from(Unexpected(c)))
+                }
+                Some('\t') => {
+                    self.advance_tab();
+                    codepoints.push('\t');
+                }
+                Some('\\') => {
+                    let escape_start = self.pos;
+                    let escape_offset = codepoints.len();
+                    self.advance_column(1);
 
                     match self.advance_input_reader()? {
+                        Some('\n') if self.options.json5 => {
+                            self.advance_line();
+                        }
+                        Some('\r') if self.options.json5 => {
+                            if self.input_reader.expect_char('\n')? {
+                                self.advance_column(1);
+                            }
+                            self.advance_line();
+                        }
                         Some(c @ ('"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't')) => {
-                            codepoints.push(c)
+                            self.advance_column(1);
+                            codepoints.push('\\');
+                            codepoints.push(c);
                         }
                         Some('u') => {
-                            let next_four = (0..4).filter_map(|i| self.input_reader.peek(i));
-                            let valid_count = next_four.filter(char::is_ascii_hexdigit).count();
-
-                            if valid_count != 4 {
-                                self.input_reader.consume(valid_count + 1)?;
-                                return Err(Error::from(Expected(Digit(Hex))));
-                            }
-
-                            codepoints.push('u');
-                            for _ in 0..4 {
-                                codepoints.push(self.advance_input_reader()?.unwrap());
-                            }
+                            self.advance_column(1);
+                            codepoints.push_str("\\u");
+                            self.match_hex_digits(4, &mut codepoints)?;
+                        }
+                        Some('x') if self.options.json5 => {
+                            self.advance_column(1);
+                            codepoints.push_str("\\x");
+                            self.match_hex_digits(2, &mut codepoints)?;
                         }
                         _ => return Err(Error::from(Expected(EscapedChar))),
                     }
+
+                    // Line continuations leave nothing behind to decode.
+                    if self.options.escape_spans {
+                        if let Some(decoded) = decode_escape(&codepoints[escape_offset..]) {
+                            escapes.push(((escape_start, self.pos), decoded));
+                        }
+                    }
+                }
+                Some(c) => {
+                    self.advance_text(c.encode_utf8(&mut [0; 4]));
+                    codepoints.push(c);
                 }
-                Some(c) => codepoints.push(c),
                 None => return Err(Error::from(Expected(StrTerminator))),
             }
         }
 
-        Ok(codepoints)
+        Ok(self.finish_raw(codepoints))
+    }
+
+    fn take_scratch(&mut self) -> String {
+        let mut scratch = mem::take(&mut self.scratch);
+        scratch.clear();
+
+        scratch
+    }
+
+    // Hands the scratch buffer back once a literal has been matched in it,
+    // returning an owned copy of the literal unless only spans are wanted.
+    fn finish_raw(&mut self, literal: String) -> String {
+        let raw = match self.spans_only {
+            true => String::new(),
+            false => String::from(literal.as_str()),
+        };
+        self.scratch = literal;
+
+        raw
+    }
+
+    fn match_hex_digits(&mut self, count: usize, codepoints: &mut String) -> Result<()> {
+        let next = (0..count).filter_map(|i| self.input_reader.peek(i));
+        let valid_count = next.filter(char::is_ascii_hexdigit).count();
+
+        if valid_count != count {
+            self.input_reader.consume(valid_count + 1)?;
+            return Err(Error::from(Expected(Digit(Hex))));
+        }
+
+        for _ in 0..count {
+            codepoints.push(self.advance_input_reader()?.unwrap());
+            self.advance_column(1);
+        }
+
+        Ok(())
     }
 }
 
@@ -411,6 +1109,10 @@ impl<R: input_reader::ReadInput> Iterator for IntoIter<R> {
     type Item = Result<Token>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if !self.lexer.started {
+            self.last_err = self.lexer.consume().err();
+        }
+
         if let Some(err) = self.last_err.take() {
             self.last_err = self.lexer.consume().err();
             return Some(Err(err));
@@ -421,4 +1123,1003 @@ impl<R: input_reader::ReadInput> Iterator for IntoIter<R> {
 
         Some(Ok(c))
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Besides the token and error already read, every item spans at least
+        // one of the characters left.
+        let pending =
+            usize::from(self.lexer.current_token.is_some()) + usize::from(self.last_err.is_some());
+        let remaining = self.lexer.input_reader.remaining();
+
+        (pending, remaining.and_then(|r| r.checked_add(pending)))
+    }
+}
+
+impl<R: input_reader::ReadInput> Iterator for SpannedTokens<R> {
+    type Item = Result<(TokenKind, Pos, Pos)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.inner.next()?;
+
+        Some(token.map(|token| (token.kind, token.start, token.end)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use input_reader::{MapReader, MemoryReader};
+
+    fn lex(source: &str) -> Result<Vec<Token>> {
+        lex_with(source, LexerOptions::default())
+    }
+
+    fn lex_with(source: &str, options: LexerOptions) -> Result<Vec<Token>> {
+        let reader = MemoryReader::new(source.as_bytes())?;
+
+        Lexer::with_options(reader, options)?.into_iter().collect()
+    }
+
+    fn json5() -> LexerOptions {
+        LexerOptions {
+            json5: true,
+            ..LexerOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_token_line_column() -> Result<()> {
+        let tokens = lex("{\n  \"a\": [1,\n\ttrue]\n}")?;
+        let positions = tokens
+            .iter()
+            .filter(|t| t.kind != Whitespace)
+            .map(|t| (t.line(), t.column()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            positions,
+            [
+                (1, 1),
+                (2, 3),
+                (2, 6),
+                (2, 8),
+                (2, 9),
+                (2, 10),
+                (3, 2),
+                (3, 6),
+                (4, 1)
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_byte_order_mark() -> Result<()> {
+        assert!(lex("\u{feff}")?.is_empty());
+
+        let tokens = lex("\u{feff}[1]")?;
+        assert_eq!(tokens.len(), 3);
+        assert_eq!((tokens[0].line(), tokens[0].column()), (1, 1));
+
+        assert!(lex("[\u{feff}1]").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_reader() -> Result<()> {
+        let reader = MemoryReader::new(r#"["json", NULL]"#.as_bytes())?;
+        let reader = MapReader::new(reader, |c| match c {
+            'a'..='z' => c.to_ascii_uppercase(),
+            'A'..='Z' => c.to_ascii_lowercase(),
+            _ => c,
+        });
+        let tokens = Lexer::new(reader)?
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+
+        assert_eq!(tokens[1].raw, "JSON");
+        assert_eq!(tokens[4].kind, Literal { kind: Null });
+        assert_eq!(tokens[4].raw, "null");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hex_escapes() -> Result<()> {
+        let tokens = lex_with(r#""\x41\xfF""#, json5())?;
+        assert_eq!(tokens[0].raw, r"\x41\xfF");
+
+        let err = lex_with(r#""\xG1""#, json5()).unwrap_err();
+        assert!(matches!(err.repr, Expected(Digit(Hex))));
+
+        let err = lex_with(r#""\x4""#, json5()).unwrap_err();
+        assert!(matches!(err.repr, Expected(Digit(Hex))));
+
+        let err = lex(r#""\x41""#).unwrap_err();
+        assert!(matches!(err.repr, Expected(EscapedChar)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_continuations() -> Result<()> {
+        for source in ["[\"a\\\nb\", 1]", "[\"a\\\r\nb\", 1]", "[\"a\\\rb\", 1]"] {
+            let tokens = lex_with(source, json5())?;
+
+            assert_eq!(tokens[1].raw, "ab");
+            assert_eq!((tokens[1].end.line, tokens[1].end.column), (2, 3));
+            assert_eq!((tokens[2].line(), tokens[2].column()), (2, 3));
+            assert_eq!((tokens[4].line(), tokens[4].column()), (2, 5));
+
+            let err = lex(source).unwrap_err();
+            assert!(matches!(err.repr, Expected(EscapedChar)));
+        }
+
+        let tokens = lex_with("\"a\\\n\\\nb\\\n\"", json5())?;
+        assert_eq!(tokens[0].raw, "ab");
+        assert_eq!((tokens[0].end.line, tokens[0].end.column), (4, 2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_error() -> Result<()> {
+        let source = "[\n\ttrue,\n\tnul\n]";
+        let err = lex(source).unwrap_err();
+
+        assert_eq!(
+            err.render(source),
+            "error: expected keyword \"null\"\n --> 3:2\n  |\n3 | \tnul\n  | \t^"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_boxed_reader() -> Result<()> {
+        let reader: Box<dyn input_reader::ReadInput> =
+            Box::new(MemoryReader::new("[true]".as_bytes())?);
+        let lexer: Lexer<Box<dyn input_reader::ReadInput>> = Lexer::new(reader)?;
+        let tokens = lexer.into_iter().collect::<Result<Vec<_>>>()?;
+
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[1].raw, "true");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_column_after_strings() -> Result<()> {
+        let cases = [
+            (r#"["a\nb", 1]"#, 8),
+            (r#"["\"\\\/", 1]"#, 10),
+            (r#"["\u0041é", 1]"#, 11),
+            (r#"["é\tü", 1]"#, 8),
+            (r#"["", 1]"#, 4),
+        ];
+
+        for (source, column) in cases {
+            let tokens = lex(source)?;
+
+            assert_eq!(tokens[1].end.column, column, "{}", source);
+            assert_eq!(tokens[2].column(), column, "{}", source);
+            assert_eq!(tokens[4].column(), column + 2, "{}", source);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_coalesce_whitespace() -> Result<()> {
+        let options = LexerOptions {
+            coalesce_whitespace: true,
+            ..LexerOptions::default()
+        };
+        let tokens = lex_with("[\n\t\t1, \r\n  2 ]  ", options)?;
+        let kinds = tokens.iter().map(|t| t.kind).collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            [
+                OpenBracket,
+                Whitespace,
+                Literal { kind: Num },
+                Comma,
+                Whitespace,
+                Literal { kind: Num },
+                Whitespace,
+                CloseBracket,
+                Whitespace,
+            ]
+        );
+        assert_eq!(tokens[1].raw, "\n\t\t");
+        assert_eq!((tokens[2].line(), tokens[2].column()), (2, 3));
+        assert_eq!(tokens[4].raw, " \r\n  ");
+        assert_eq!((tokens[5].line(), tokens[5].column()), (3, 3));
+        assert_eq!(tokens[8].raw, "  ");
+
+        let source = " ".repeat(100);
+        let reader = input_reader::BufferedReader::new(source.as_bytes())?;
+        let options = LexerOptions {
+            coalesce_whitespace: true,
+            ..LexerOptions::default()
+        };
+        let tokens = Lexer::with_options(reader, options)?
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].raw.len(), 100);
+        assert_eq!(tokens[0].end.column, 101);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_kind() -> Result<()> {
+        let hex = ErrorKind::Expected(Digit(Hex));
+        let dec = ErrorKind::Expected(Digit(Dec));
+
+        assert_eq!(lex(r#""\u12G4""#).unwrap_err().kind(), hex);
+        assert_eq!(lex(r#""\u12""#).unwrap_err().kind(), hex);
+        assert_eq!(lex("-x").unwrap_err().kind(), dec);
+        assert_eq!(lex("1.e5").unwrap_err().kind(), dec);
+        assert_eq!(lex("1e+").unwrap_err().kind(), dec);
+        assert_eq!(
+            lex("nil").unwrap_err().kind(),
+            ErrorKind::Expected(Keyword("null"))
+        );
+        assert_eq!(lex("?").unwrap_err().kind(), ErrorKind::Unexpected('?'));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_chain_reader() -> Result<()> {
+        let reader = input_reader::ChainReader::new(vec![
+            MemoryReader::new(r#"["jso"#.as_bytes())?,
+            MemoryReader::new(r#"n", nu"#.as_bytes())?,
+            MemoryReader::new("ll]".as_bytes())?,
+        ]);
+        let tokens = Lexer::new(reader)?
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+
+        assert_eq!(tokens[1].raw, "json");
+        assert_eq!(tokens[4].raw, "null");
+        assert_eq!(tokens[5].kind, CloseBracket);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_position() -> Result<()> {
+        let reader = MemoryReader::new("[1,\n  true]".as_bytes())?;
+        let mut lexer = Lexer::new(reader)?;
+
+        assert_eq!((lexer.position().line(), lexer.position().column()), (1, 2));
+        for _ in 0..6 {
+            lexer.consume()?;
+        }
+        assert_eq!(lexer.peek().map(|t| t.kind), Some(Literal { kind: Bool }));
+        assert_eq!(lexer.position().line(), 2);
+        assert_eq!(lexer.position().column(), 7);
+        assert_eq!(lexer.position().offset(), 10);
+        lexer.consume()?;
+        lexer.consume()?;
+        assert!(lexer.peek().is_none());
+        assert_eq!(lexer.position().column(), 8);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_token_too_long() -> Result<()> {
+        let long_string = format!("\"{}\"", "json".repeat(8));
+        let reader = input_reader::BufferedReader::with_capacity(2, long_string.as_bytes())?;
+        let tokens = Lexer::new(reader)?
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(tokens[0].raw, "json".repeat(8));
+
+        let reader = input_reader::BufferedReader::with_capacity(2, "[true]".as_bytes())?;
+        let err = Lexer::new(reader)?
+            .into_iter()
+            .collect::<Result<Vec<_>>>()
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TokenTooLong);
+        assert_eq!(
+            err.render("[true]"),
+            "error: token is too long for the input reader's buffer\n --> 1:2\n  |\n1 | [true]\n  |  ^"
+        );
+
+        assert_eq!(
+            lex("nu").unwrap_err().kind(),
+            ErrorKind::Expected(Keyword("null"))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_leading_decimal_point() -> Result<()> {
+        let options = || LexerOptions {
+            allow_leading_decimal_point: true,
+            ..LexerOptions::default()
+        };
+        let dec = ErrorKind::Expected(Digit(Dec));
+
+        assert_eq!(lex(".5").unwrap_err().kind(), ErrorKind::Unexpected('.'));
+        assert_eq!(lex("5.").unwrap_err().kind(), dec);
+        assert_eq!(lex(".").unwrap_err().kind(), ErrorKind::Unexpected('.'));
+
+        for (source, raw) in [
+            (".5", ".5"),
+            ("5.", "5."),
+            ("-.5e1", "-.5e1"),
+            ("5.e1", "5.e1"),
+        ] {
+            let tokens = lex_with(source, options())?;
+            assert_eq!(tokens[0].kind, Literal { kind: Num });
+            assert_eq!(tokens[0].raw, raw);
+        }
+        assert_eq!(lex_with(".", options()).unwrap_err().kind(), dec);
+        assert_eq!(lex_with("-.", options()).unwrap_err().kind(), dec);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_number_too_long() -> Result<()> {
+        let options = || LexerOptions {
+            max_number_len: 8,
+            ..LexerOptions::default()
+        };
+
+        let tokens = lex_with("-123.5e6", options())?;
+        assert_eq!(tokens[0].raw, "-123.5e6");
+
+        let err = lex_with("[123456789]", options()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NumberTooLong);
+        assert_eq!(
+            err.to_string(),
+            "number literal exceeds the limit of 8 characters"
+        );
+        assert_eq!(err.pos.map(|pos| pos.column()), Some(2));
+
+        let source = format!("1{}", "0".repeat(10_000));
+        assert_eq!(lex(&source).unwrap_err().kind(), ErrorKind::NumberTooLong);
+        let source = format!("1e{}", "9".repeat(10_000));
+        assert_eq!(lex(&source).unwrap_err().kind(), ErrorKind::NumberTooLong);
+        let source = format!("1e1{}", "_".repeat(10_000));
+        assert_eq!(lex(&source).unwrap_err().kind(), ErrorKind::NumberTooLong);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_strings_reuse_scratch() -> Result<()> {
+        let tokens = lex(r#"["a long first string", "b", "", "c\u0041", "d"]"#)?;
+        let strings = tokens
+            .iter()
+            .filter(|t| t.kind() == Literal { kind: Str })
+            .map(Token::normalized)
+            .collect::<Vec<_>>();
+
+        assert_eq!(strings, ["a long first string", "b", "", r"c\u0041", "d"]);
+
+        // A string that fails midway leaves nothing behind for the next one.
+        let reader = MemoryReader::new("[\"broken\n\"ok\"".as_bytes())?;
+        let mut lexer = Lexer::new(reader)?;
+        assert!(lexer.consume().is_err());
+        lexer.consume()?;
+        assert_eq!(lexer.peek().map(Token::normalized), Some("ok"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_too_long() -> Result<()> {
+        let options = || LexerOptions {
+            max_string_len: 4,
+            ..LexerOptions::default()
+        };
+
+        let tokens = lex_with(r#""jsön""#, options())?;
+        assert_eq!(tokens[0].raw, "jsön");
+        let tokens = lex_with(r#""\n""#, options())?;
+        assert_eq!(tokens[0].raw, "\\n");
+
+        let err = lex_with(r#"["jsons"]"#, options()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::StringTooLong);
+        assert_eq!(
+            err.to_string(),
+            "string literal exceeds the limit of 4 characters"
+        );
+        assert_eq!(err.pos.map(|pos| pos.column()), Some(2));
+
+        let err = lex_with(r#""\u0041""#, options()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::StringTooLong);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invisible_chars() -> Result<()> {
+        let err = lex("[1,\u{200b}2]").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unexpected('\u{200b}'));
+        assert_eq!(err.to_string(), "unexpected character U+200B");
+
+        let err = lex("[\u{feff}]").unwrap_err();
+        assert_eq!(err.to_string(), "unexpected character U+FEFF");
+        let err = lex("\"\u{1}\"").unwrap_err();
+        assert_eq!(err.to_string(), "unexpected character U+0001");
+        let err = lex("?").unwrap_err();
+        assert_eq!(err.to_string(), "unexpected character '?'");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_separators() -> Result<()> {
+        let source = "[1,\u{2028}2\u{2029}]";
+        let tokens = lex_with(source, json5())?;
+        let spans = tokens
+            .iter()
+            .map(|t| {
+                (
+                    t.kind(),
+                    t.start().line(),
+                    t.start().column(),
+                    t.start().offset(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            spans,
+            [
+                (OpenBracket, 1, 1, 0),
+                (Literal { kind: Num }, 1, 2, 1),
+                (Comma, 1, 3, 2),
+                (Whitespace, 1, 4, 3),
+                (Literal { kind: Num }, 2, 1, 6),
+                (Whitespace, 2, 2, 7),
+                (CloseBracket, 3, 1, 10),
+            ]
+        );
+
+        let err = lex(source).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unexpected('\u{2028}'));
+        assert_eq!(err.to_string(), "unexpected character U+2028");
+        assert_eq!(lex("\"\u{2028}\u{2029}\"")?[0].raw, "\u{2028}\u{2029}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_spanned_tokens() -> Result<()> {
+        let reader = MemoryReader::new("{\"a\":\n [1.5, null]}".as_bytes())?;
+        let spans = Lexer::new(reader)?
+            .spanned_tokens()
+            .map(|span| span.map(|(kind, start, end)| (kind, start.offset(), end.offset())))
+            .collect::<Result<Vec<_>>>()?;
+
+        assert_eq!(
+            spans,
+            [
+                (OpenBrace, 0, 1),
+                (Literal { kind: Str }, 1, 4),
+                (Colon, 4, 5),
+                (Whitespace, 5, 6),
+                (Whitespace, 6, 7),
+                (OpenBracket, 7, 8),
+                (Literal { kind: Num }, 8, 11),
+                (Comma, 11, 12),
+                (Whitespace, 12, 13),
+                (Literal { kind: Null }, 13, 17),
+                (CloseBracket, 17, 18),
+                (CloseBrace, 18, 19),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_spanned_tokens_keep_no_text() -> Result<()> {
+        let reader = MemoryReader::new("{\"key\": [\"a\\u00e9\", -1.5e3, true, x]}\n".as_bytes())?;
+        let spanned_tokens = Lexer::lazy_with_options(reader, json5()).spanned_tokens();
+
+        for token in spanned_tokens.inner {
+            let token = token?;
+            assert!(
+                matches!(token.raw, Cow::Borrowed(_)) || token.raw.is_empty(),
+                "{:?}",
+                token
+            );
+            assert_eq!(token.source, None);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_number_source() -> Result<()> {
+        let tokens = lex_with("[+1_000, 2_5.0_1, -3]", json5())?;
+
+        assert_eq!(tokens[1].raw_source(), "+1_000");
+        assert_eq!(tokens[1].normalized(), "1000");
+        assert_eq!(tokens[4].raw_source(), "2_5.0_1");
+        assert_eq!(tokens[4].normalized(), "25.01");
+        assert_eq!(tokens[7].raw_source(), "-3");
+        assert_eq!(tokens[7].normalized(), "-3");
+        assert_eq!(tokens[8].start().column(), 21);
+
+        assert_eq!(lex("+1").unwrap_err().kind(), ErrorKind::Unexpected('+'));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_identifiers() -> Result<()> {
+        let kinds = |source| -> Result<Vec<_>> {
+            let tokens = lex_with(source, json5())?;
+            Ok(tokens.into_iter().map(|t| (t.kind, t.raw)).collect())
+        };
+
+        assert_eq!(
+            kinds("{a:1}")?,
+            [
+                (OpenBrace, "{".into()),
+                (Identifier, "a".into()),
+                (Colon, ":".into()),
+                (Literal { kind: Num }, "1".into()),
+                (CloseBrace, "}".into()),
+            ]
+        );
+        assert_eq!(kinds("{$b_2: true}")?[1], (Identifier, "$b_2".into()));
+        assert_eq!(
+            kinds("{$b_2: true}")?[4],
+            (Literal { kind: Bool }, "true".into())
+        );
+        assert_eq!(
+            kinds("{nullable: null}")?[1],
+            (Identifier, "nullable".into())
+        );
+
+        assert_eq!(lex("{a:1}").unwrap_err().kind(), ErrorKind::Unexpected('a'));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_escape_spans() -> Result<()> {
+        let options = LexerOptions {
+            escape_spans: true,
+            ..LexerOptions::default()
+        };
+        let tokens = lex_with(r#""a\n\u0041b""#, options)?;
+        let escapes = tokens[0]
+            .escapes()
+            .iter()
+            .map(|&((start, end), c)| (start.column(), end.column(), c))
+            .collect::<Vec<_>>();
+
+        assert_eq!(escapes, [(3, 5, '\n'), (5, 11, 'A')]);
+        assert!(lex(r#""a\n\u0041b""#)?[0].escapes().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hex_case() -> Result<()> {
+        let with_case = |hex_case| LexerOptions {
+            escape_spans: true,
+            hex_case,
+            ..LexerOptions::default()
+        };
+
+        let tokens = lex_with(r#"["\u00E9", "\u00e9"]"#, with_case(Some(HexCase::Lower)))?;
+        assert_eq!(tokens[1].normalized(), r"\u00e9");
+        assert_eq!(tokens[1].raw_source(), r"\u00E9");
+        assert_eq!(tokens[1].escapes()[0].1, 'é');
+        assert_eq!(tokens[4].normalized(), r"\u00e9");
+        assert_eq!(tokens[4].escapes()[0].1, 'é');
+
+        let tokens = lex_with(r#""\u00e9\n""#, with_case(Some(HexCase::Upper)))?;
+        assert_eq!(tokens[0].normalized(), r"\u00E9\n");
+        assert_eq!(tokens[0].raw_source(), r"\u00e9\n");
+
+        let tokens = lex_with(r#""\u00E9""#, with_case(None))?;
+        assert_eq!(tokens[0].normalized(), r"\u00E9");
+
+        let options = LexerOptions {
+            hex_case: Some(HexCase::Lower),
+            ..json5()
+        };
+        assert_eq!(
+            lex_with(r#""\xAB\\xCD""#, options)?[0].normalized(),
+            r"\xab\\xCD"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tab_width() -> Result<()> {
+        let source = "[\n\t\ttrue]";
+        let position_of_true = |options| -> Result<(usize, usize)> {
+            let tokens = lex_with(source, options)?;
+            let token = tokens.iter().find(|t| t.kind == Literal { kind: Bool });
+            Ok(token
+                .map(|t| (t.column(), t.end().offset()))
+                .unwrap_or_default())
+        };
+
+        assert_eq!(position_of_true(LexerOptions::default())?, (3, 8));
+        let options = LexerOptions {
+            tab_width: 4,
+            ..LexerOptions::default()
+        };
+        assert_eq!(position_of_true(options)?, (9, 8));
+        let options = LexerOptions {
+            tab_width: 4,
+            coalesce_whitespace: true,
+            ..LexerOptions::default()
+        };
+        assert_eq!(position_of_true(options)?, (9, 8));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_tab_width() -> Result<()> {
+        let source = "\t\t?";
+        let options = LexerOptions {
+            tab_width: 4,
+            ..LexerOptions::default()
+        };
+        let reader = MemoryReader::new(source.as_bytes())?;
+        let err = Lexer::with_options(reader, options)?
+            .collect_tokens()
+            .unwrap_err();
+
+        assert_eq!(
+            err.render(source),
+            "error: unexpected character '?'\n --> 1:9\n  |\n1 | \t\t?\n  | \t\t^"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_new_lazy() -> Result<()> {
+        use std::io;
+
+        #[derive(Debug)]
+        struct Failing;
+
+        impl input_reader::ReadInput for Failing {
+            fn peek(&self, _k: usize) -> Option<char> {
+                Some('[')
+            }
+
+            fn consume(&mut self, _k: usize) -> input_reader::Result<()> {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed").into())
+            }
+
+            fn last_consumed(&self) -> Option<char> {
+                None
+            }
+
+            fn bytes_consumed(&self) -> usize {
+                0
+            }
+        }
+
+        assert!(Lexer::new(Failing).is_err());
+
+        let lexer = Lexer::new_lazy(Failing);
+        assert!(lexer.peek().is_none());
+        assert!(!lexer.is_started());
+        let err = lexer.into_iter().next().map(|token| token.unwrap_err());
+        assert_eq!(err.map(|err| err.kind()), Some(ErrorKind::InputReader));
+
+        let reader = MemoryReader::new("\u{feff}[true]".as_bytes())?;
+        let mut lexer = Lexer::new_lazy(reader);
+        lexer.consume()?;
+        assert!(lexer.is_started());
+        assert_eq!(lexer.peek().map(|t| t.kind), Some(OpenBracket));
+
+        let mut lexer = Lexer::lazy_with_options(MemoryReader::new("{a: 1}".as_bytes())?, json5());
+        assert!(!lexer.is_started());
+        lexer.consume()?;
+        lexer.consume()?;
+        assert_eq!(lexer.peek().map(|t| t.kind), Some(Identifier));
+
+        let mut lexer = Lexer::new_lazy(MemoryReader::new("".as_bytes())?);
+        lexer.consume()?;
+        assert!(lexer.is_started() && lexer.peek().is_none());
+        let tokens = Lexer::new_lazy(MemoryReader::new("[true]".as_bytes())?)
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(tokens.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stats() -> Result<()> {
+        let reader = MemoryReader::new("{\"a\": [1, 2],\n \"b\": null}\n".as_bytes())?;
+        let mut lexer = Lexer::new(reader)?;
+
+        assert_eq!(
+            lexer.stats(),
+            LexStats {
+                tokens: 1,
+                bytes: 1,
+                lines: 1
+            }
+        );
+        while lexer.peek().is_some() {
+            lexer.consume()?;
+        }
+        assert_eq!(
+            lexer.stats(),
+            LexStats {
+                tokens: 19,
+                bytes: 26,
+                lines: 3
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_allow_control_chars() -> Result<()> {
+        let err = lex("\"a\tb\"").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unexpected('\t'));
+        assert_eq!(err.to_string(), "unexpected character U+0009");
+
+        let options = LexerOptions {
+            allow_control_chars: true,
+            tab_width: 4,
+            ..LexerOptions::default()
+        };
+        let reader = MemoryReader::new("\"a\tb\" 1".as_bytes())?;
+        let tokens = Lexer::with_options(reader, options)?
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(tokens[0].raw, "a\tb");
+        assert_eq!(tokens[2].column(), 10);
+
+        let options = LexerOptions {
+            allow_control_chars: true,
+            ..LexerOptions::default()
+        };
+        let reader = MemoryReader::new("\"a\nb\"".as_bytes())?;
+        let err = Lexer::with_options(reader, options).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unexpected('\n'));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_classification() -> Result<()> {
+        let options = LexerOptions {
+            json5: true,
+            ..LexerOptions::default()
+        };
+        let reader = MemoryReader::new("{a: [1, \"b\", null]}".as_bytes())?;
+        let classes = Lexer::with_options(reader, options)?
+            .into_iter()
+            .map(|token| {
+                token.map(
+                    |t| match (t.is_structural(), t.is_value(), t.is_whitespace()) {
+                        (true, false, false) => 's',
+                        (false, true, false) => 'v',
+                        (false, false, true) => 'w',
+                        (false, false, false) => '-',
+                        _ => '?',
+                    },
+                )
+            })
+            .collect::<Result<String>>()?;
+
+        assert_eq!(classes, "s-swsvswvswvss");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_size_hint() -> Result<()> {
+        let reader = MemoryReader::new("[null, 1]".as_bytes())?;
+        let mut tokens = Lexer::new(reader)?.into_iter();
+        assert_eq!(tokens.size_hint(), (1, Some(9)));
+
+        tokens.next();
+        tokens.next();
+        assert_eq!(tokens.size_hint(), (1, Some(4)));
+        assert_eq!(tokens.count(), 4);
+
+        let reader = MemoryReader::new("[\"a\", true]".as_bytes())?;
+        let tokens = Lexer::new_lazy(reader).into_iter();
+        let (_, upper) = tokens.size_hint();
+        assert_eq!(upper, Some(11));
+        assert!(tokens.count() <= 11);
+
+        let reader = MapReader::new(MemoryReader::new("{}".as_bytes())?, |c| c);
+        assert_eq!(Lexer::new(reader)?.into_iter().size_hint(), (1, Some(2)));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_size_hint_unknown_remaining() -> Result<()> {
+        let source = "[1, 2, 3, 4, 5, 6, 7, 8, 9]";
+        let reader = input_reader::BufferedReader::new(source.as_bytes())?;
+        assert_eq!(Lexer::new(reader)?.into_iter().size_hint(), (1, None));
+
+        // Nothing is left to read once the end of input has been reached.
+        let reader = input_reader::BufferedReader::new("[]".as_bytes())?;
+        let mut tokens = Lexer::new(reader)?.into_iter();
+        tokens.next();
+        tokens.next();
+        assert_eq!(tokens.size_hint(), (0, Some(0)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_collect_tokens() -> Result<()> {
+        let reader = MemoryReader::new("[null, 1]".as_bytes())?;
+        let kinds = Lexer::new(reader)?
+            .collect_tokens()?
+            .iter()
+            .map(Token::kind)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                OpenBracket,
+                Literal { kind: Null },
+                Comma,
+                Whitespace,
+                Literal { kind: Num },
+                CloseBracket
+            ]
+        );
+
+        let reader = MemoryReader::new("[null, nil]".as_bytes())?;
+        let err = Lexer::new(reader)?.collect_tokens().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Expected(Keyword("null")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_whitespace_run_tokens() -> Result<()> {
+        let source = "{\n        \"a\": [\n\t\t1,   \r\n                2\n        ]\n}\n";
+        let options = || LexerOptions {
+            coalesce_whitespace: true,
+            ..LexerOptions::default()
+        };
+        let spans = |tokens: Vec<Token>| {
+            tokens
+                .into_iter()
+                .map(|t| (t.kind, t.raw, t.start, t.end))
+                .collect::<Vec<_>>()
+        };
+
+        // A `MapReader` only ever peeks, so it takes the scalar path.
+        let scalar = MapReader::new(MemoryReader::new(source.as_bytes())?, |c| c);
+        let scalar = Lexer::with_options(scalar, options())?.collect_tokens()?;
+        let reader = MemoryReader::new(source.as_bytes())?;
+        let tokens = Lexer::with_options(reader, options())?.collect_tokens()?;
+
+        assert_eq!(tokens.len(), 16);
+        assert_eq!(spans(tokens), spans(scalar));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_literal() -> Result<()> {
+        let undefined = || LexerOptions {
+            custom_literal: Some(Box::new(
+                |first, reader: &mut dyn input_reader::ReadInput| {
+                    const REST: &str = "ndefined";
+
+                    let matches = REST
+                        .chars()
+                        .enumerate()
+                        .all(|(k, c)| reader.peek(k) == Some(c));
+                    if first != 'u' || !matches {
+                        return Ok(None);
+                    }
+                    reader.consume(REST.len())?;
+
+                    Ok(Some((Literal { kind: Null }, String::from("undefined"))))
+                },
+            )),
+            ..LexerOptions::default()
+        };
+
+        let tokens = lex_with("[undefined, 1]", undefined())?;
+        assert_eq!(tokens[1].kind, Literal { kind: Null });
+        assert_eq!(tokens[1].raw, "undefined");
+        assert_eq!((tokens[1].column(), tokens[1].end().column()), (2, 11));
+        assert_eq!(tokens[4].column(), 13);
+
+        let err = lex_with("[undef]", undefined()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unexpected('u'));
+        let err = lex_with("[?]", undefined()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unexpected('?'));
+        let err = lex("[undefined]").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unexpected('u'));
+
+        assert!(format!("{:?}", undefined()).contains("custom_literal: true"));
+        assert!(format!("{:?}", LexerOptions::default()).contains("custom_literal: false"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_options_are_send_and_sync() {
+        fn assert_send<T: Send>() {}
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<LexerOptions>();
+        assert_send::<Lexer<MemoryReader>>();
+    }
+
+    #[test]
+    fn test_byte_offsets() -> Result<()> {
+        for source in [
+            "[\"é\", null, 1]",
+            "\u{feff}{\"ö\\u00e9\": \"😀\", \"b\": true}",
+        ] {
+            let tokens = lex(source)?;
+            for token in &tokens {
+                let (start, end) = (token.start().offset(), token.end().offset());
+                assert!(source[start..end].contains(token.raw_source()));
+            }
+        }
+
+        let tokens = lex("[\"éé\", null, 1]")?;
+        assert_eq!(tokens[4].kind, Literal { kind: Null });
+        assert_eq!(tokens[4].start().offset(), 9);
+        assert_eq!(tokens[4].column(), 8);
+        assert_eq!(tokens[7].start().offset(), 15);
+        assert_eq!(tokens[7].column(), 14);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detached_exponent() -> Result<()> {
+        for source in ["1 e5", "[-2.5\n  E-3]", "{\"a\": 0\tE+1}"] {
+            let err = lex(source).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::DetachedExponent);
+            assert_eq!(
+                err.to_string(),
+                "exponent must immediately follow the number, no whitespace allowed"
+            );
+        }
+
+        assert_eq!(lex("[1 e]").unwrap_err().kind(), ErrorKind::Unexpected('e'));
+        assert_eq!(lex("1 ex").unwrap_err().kind(), ErrorKind::Unexpected('e'));
+        assert_eq!(lex("[1, 2 ]")?.len(), 7);
+
+        Ok(())
+    }
 }
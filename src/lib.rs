@@ -1,5 +1,27 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![warn(missing_debug_implementations, rust_2018_idioms)]
 #![feature(read_initializer, const_io_structs, trusted_len)]
 
+extern crate alloc;
+
 pub mod input_reader;
 pub mod lexer;
+pub mod parser;
+
+#[cfg(all(test, not(feature = "std")))]
+mod tests {
+    use crate::{input_reader::MemoryReader, lexer::Lexer, parser};
+
+    #[test]
+    fn test_no_std() -> parser::Result<()> {
+        let source: &[u8] = br#"{"a": [1, "b\u0063", null]}"#;
+
+        let tokens = Lexer::new(MemoryReader::new(source)?)?
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(tokens.len(), 14);
+        parser::validate(MemoryReader::new(source)?, 128)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1163 @@
+//! Structural validation of JSON documents on top of the [`Lexer`].
+//!
+//! The `json::parser` module drives a [`Lexer`] over an input reader and
+//! checks the token stream against the JSON grammar: balanced braces and
+//! brackets, colons between keys and values, commas between elements and a
+//! single top-level value.
+//!
+//! No value tree is built along the way, which makes [`validate`] and
+//! [`is_valid_json`] cheap to run on large documents. Callers wanting the
+//! contents of a document can have [`visit`] report them to a [`Visitor`] as
+//! they are parsed and build their own types from there.
+//!
+//! # Examples
+//!
+//! ```
+//! use json::{input_reader::MemoryReader, parser};
+//!
+//! fn main() -> parser::Result<()> {
+//!     let reader = MemoryReader::new(r#"{"list": [1, 2, 3]}"#.as_bytes())?;
+//!     parser::validate(reader, 128)?;
+//!
+//!     let reader = MemoryReader::new(r#"{"list": [1, 2, 3}"#.as_bytes())?;
+//!     assert!(!parser::is_valid_json(reader, 128));
+//!
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`Lexer`]: crate::lexer::Lexer
+
+use alloc::{format, string::String, vec::Vec};
+use core::{char, fmt, result};
+#[cfg(feature = "std")]
+use std::error;
+
+use crate::{
+    input_reader,
+    lexer::{self, Bool, Lexer, LexerOptions, Null, Num, Pos, Str, TokenKind},
+};
+
+/// A specialized [`Result`] type for parsing operations.
+///
+/// [`Result`]: std::result::Result
+pub type Result<T> = result::Result<T, Error>;
+
+/// The error type for parsing operations.
+///
+/// Errors either come from the underlying [`Lexer`] or describe a token
+/// stream that doesn't form a valid JSON document.
+///
+/// [`Lexer`]: crate::lexer::Lexer
+#[derive(Debug, Clone)]
+pub struct Error {
+    repr: Repr,
+    pos: Option<Pos>,
+}
+
+#[derive(Debug, Clone)]
+enum Repr {
+    Lexer(lexer::Error),
+    Truncated(lexer::Error),
+    Expected(ExpectedKind),
+    UnexpectedEof,
+    EmptyInput,
+    DepthLimitExceeded(usize),
+    MismatchedClose(Container, Pos),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ExpectedKind {
+    Value,
+    Key,
+    Colon,
+    CommaOrCloseBrace,
+    CommaOrCloseBracket,
+    Eof,
+}
+
+use ExpectedKind::{Colon, CommaOrCloseBrace, CommaOrCloseBracket, Eof, Key, Value};
+use Repr::{DepthLimitExceeded, EmptyInput, Expected, MismatchedClose, UnexpectedEof};
+
+impl From<lexer::Error> for Error {
+    fn from(error: lexer::Error) -> Self {
+        Self {
+            repr: Repr::Lexer(error),
+            pos: None,
+        }
+    }
+}
+
+impl From<input_reader::Error> for Error {
+    fn from(error: input_reader::Error) -> Self {
+        Self::from(lexer::Error::from(error))
+    }
+}
+
+impl From<Repr> for Error {
+    fn from(repr: Repr) -> Self {
+        Self { repr, pos: None }
+    }
+}
+
+impl Error {
+    /// Returns whether parsing could sensibly continue past this error.
+    ///
+    /// Syntax errors such as a missing comma or an unexpected character are
+    /// recoverable, since the rest of the input can still be read. Input
+    /// reading failures, exceeded limits and a prematurely ended input are
+    /// fatal, including input ending in the middle of a token such as an
+    /// unterminated string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::{input_reader::MemoryReader, parser};
+    ///
+    /// fn main() -> parser::Result<()> {
+    ///     let reader = MemoryReader::new("[1 2]".as_bytes())?;
+    ///     assert!(parser::validate(reader, 128).unwrap_err().recoverable());
+    ///
+    ///     let reader = MemoryReader::new("[1, 2".as_bytes())?;
+    ///     assert!(!parser::validate(reader, 128).unwrap_err().recoverable());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn recoverable(&self) -> bool {
+        match &self.repr {
+            Repr::Lexer(lexer_err) => matches!(
+                lexer_err.kind(),
+                lexer::ErrorKind::Expected(_)
+                    | lexer::ErrorKind::Unexpected(_)
+                    | lexer::ErrorKind::DetachedExponent
+            ),
+            Repr::Truncated(_lexer_err) => false,
+            Expected(_expected_kind) => true,
+            MismatchedClose(_container, _opened_at) => true,
+            UnexpectedEof => false,
+            EmptyInput => false,
+            DepthLimitExceeded(_max_depth) => false,
+        }
+    }
+
+    /// Returns whether this error was caused by an input holding no value at
+    /// all, being empty or made only of whitespace and a byte order mark.
+    ///
+    /// See [`validate_optional`] for treating such input as valid instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::{input_reader::MemoryReader, parser};
+    ///
+    /// fn main() -> parser::Result<()> {
+    ///     let reader = MemoryReader::new(" \n".as_bytes())?;
+    ///     assert!(parser::validate(reader, 128).unwrap_err().is_empty_input());
+    ///
+    ///     let reader = MemoryReader::new("[".as_bytes())?;
+    ///     assert!(!parser::validate(reader, 128).unwrap_err().is_empty_input());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub const fn is_empty_input(&self) -> bool {
+        matches!(self.repr, EmptyInput)
+    }
+
+    /// Renders this error as a multi-line diagnostic pointing at the
+    /// offending line and column of `source`, which should be the text the
+    /// error originated from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json::{input_reader::MemoryReader, parser};
+    ///
+    /// fn main() -> parser::Result<()> {
+    ///     let source = "{\n  \"a\" 1\n}";
+    ///     let reader = MemoryReader::new(source.as_bytes())?;
+    ///     let err = parser::validate(reader, 128).unwrap_err();
+    ///
+    ///     assert_eq!(
+    ///         err.render(source),
+    ///         "error: expected ':'\n --> 2:7\n  |\n2 |   \"a\" 1\n  |       ^"
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        match (&self.repr, self.pos) {
+            (Repr::Lexer(lexer_err) | Repr::Truncated(lexer_err), _) => lexer_err.render(source),
+            (_, Some(pos)) => pos.render(self, source),
+            (_, None) => format!("error: {}", self),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.repr {
+            Repr::Lexer(lexer_err) | Repr::Truncated(lexer_err) => write!(f, "{}", lexer_err),
+            Expected(expected_kind) => match expected_kind {
+                Value => write!(f, "expected value"),
+                Key => write!(f, "expected object key"),
+                Colon => write!(f, "expected ':'"),
+                CommaOrCloseBrace => write!(f, "expected ',' or '}}'"),
+                CommaOrCloseBracket => write!(f, "expected ',' or ']'"),
+                Eof => write!(f, "expected end of input"),
+            },
+            MismatchedClose(container, opened_at) => {
+                let (open, close, found) = match container {
+                    Object => ('{', '}', ']'),
+                    Array => ('[', ']', '}'),
+                };
+                write!(
+                    f,
+                    "expected '{}' to match '{}' at {}:{}, found '{}'",
+                    close,
+                    open,
+                    opened_at.line(),
+                    opened_at.column(),
+                    found
+                )?;
+                match self.pos {
+                    Some(pos) => write!(f, " at {}:{}", pos.line(), pos.column()),
+                    None => Ok(()),
+                }
+            }
+            UnexpectedEof => write!(f, "unexpected end of input"),
+            EmptyInput => write!(f, "expected value, found empty input"),
+            DepthLimitExceeded(max_depth) => {
+                write!(f, "nesting depth exceeds the limit of {}", max_depth)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match &self.repr {
+            Repr::Lexer(lexer_err) | Repr::Truncated(lexer_err) => Some(lexer_err),
+            Expected(_expected_kind) => None,
+            MismatchedClose(_container, _opened_at) => None,
+            UnexpectedEof => None,
+            EmptyInput => None,
+            DepthLimitExceeded(_max_depth) => None,
+        }
+    }
+}
+
+/// Checks that the input forms a single valid JSON document.
+///
+/// Objects and arrays may be nested at most `max_depth` levels deep, so a
+/// bare scalar is valid even with a `max_depth` of 0 while `[]` requires at
+/// least 1. Open containers are tracked on the heap rather than through
+/// recursion, so `max_depth` is a policy limit and not a guard against stack
+/// overflow.
+///
+/// # Errors
+///
+/// This function fails on the first lexing or structural error it
+/// encounters, or once the nesting depth exceeds `max_depth`. Input holding
+/// no value at all fails with an error for which [`Error::is_empty_input`]
+/// returns `true`.
+///
+/// # Examples
+///
+/// ```
+/// use json::{input_reader::MemoryReader, parser};
+///
+/// fn main() -> parser::Result<()> {
+///     let reader = MemoryReader::new("[[true], null]".as_bytes())?;
+///     parser::validate(reader, 2)?;
+///
+///     let reader = MemoryReader::new("[[true], null]".as_bytes())?;
+///     assert!(parser::validate(reader, 1).is_err());
+///
+///     Ok(())
+/// }
+/// ```
+pub fn validate<R: input_reader::ReadInput>(reader: R, max_depth: usize) -> Result<()> {
+    let mut parser = Parser::new(reader, max_depth, LexerOptions::default())?;
+
+    parser.parse_value()?;
+    parser.parse_eof()
+}
+
+/// Checks that the input forms a single valid document of the dialect
+/// described by `options`, such as JSON5.
+///
+/// With [`LexerOptions::json5`] set, unquoted identifiers are accepted as
+/// object keys. Otherwise, this behaves like [`validate`], which uses the
+/// default, strict options.
+///
+/// # Errors
+///
+/// This function fails under the same conditions as [`validate`].
+///
+/// # Examples
+///
+/// ```
+/// use json::{input_reader::MemoryReader, lexer::LexerOptions, parser};
+///
+/// fn main() -> parser::Result<()> {
+///     let json5 = || LexerOptions {
+///         json5: true,
+///         ..LexerOptions::default()
+///     };
+///
+///     let reader = MemoryReader::new("{a: 1}".as_bytes())?;
+///     parser::validate_with(reader, 128, json5())?;
+///
+///     let reader = MemoryReader::new("{a: 1}".as_bytes())?;
+///     assert!(parser::validate_with(reader, 128, LexerOptions::default()).is_err());
+///
+///     Ok(())
+/// }
+/// ```
+pub fn validate_with<R: input_reader::ReadInput>(
+    reader: R,
+    max_depth: usize,
+    options: LexerOptions,
+) -> Result<()> {
+    let mut parser = Parser::new(reader, max_depth, options)?;
+
+    parser.parse_value()?;
+    parser.parse_eof()
+}
+
+/// Checks that the input is either empty or forms a single valid JSON
+/// document, returning whether it held a document.
+///
+/// This behaves like [`validate`], except that input made only of
+/// whitespace and a byte order mark, if any, is accepted as holding no
+/// value rather than rejected, for callers where empty means absent.
+///
+/// # Errors
+///
+/// This function fails under the same conditions as [`validate`], except for
+/// empty input.
+///
+/// # Examples
+///
+/// ```
+/// use json::{input_reader::MemoryReader, parser};
+///
+/// fn main() -> parser::Result<()> {
+///     let reader = MemoryReader::new("  ".as_bytes())?;
+///     assert!(!parser::validate_optional(reader, 128)?);
+///
+///     let reader = MemoryReader::new("[1, 2]".as_bytes())?;
+///     assert!(parser::validate_optional(reader, 128)?);
+///
+///     Ok(())
+/// }
+/// ```
+pub fn validate_optional<R: input_reader::ReadInput>(reader: R, max_depth: usize) -> Result<bool> {
+    let mut parser = Parser::new(reader, max_depth, LexerOptions::default())?;
+    if parser.peek()?.is_none() {
+        return Ok(false);
+    }
+
+    parser.parse_value()?;
+    parser.parse_eof()?;
+
+    Ok(true)
+}
+
+/// Parses the input as a single JSON document, reporting its contents to
+/// `visitor` along the way.
+///
+/// Values are reported in document order, with strings and keys unescaped.
+/// The document is validated the same way as by [`validate`], but since the
+/// visitor is driven while parsing, it may have seen part of the document by
+/// the time an error is found.
+///
+/// # Errors
+///
+/// This function fails under the same conditions as [`validate`].
+///
+/// # Examples
+///
+/// ```
+/// use json::{input_reader::MemoryReader, parser::{self, Visitor}};
+///
+/// #[derive(Default)]
+/// struct CountKeys(usize);
+///
+/// impl Visitor for CountKeys {
+///     fn visit_key(&mut self, _key: &str) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// fn main() -> parser::Result<()> {
+///     let reader = MemoryReader::new(r#"{"a": {"b": 1}, "c": []}"#.as_bytes())?;
+///     let mut visitor = CountKeys::default();
+///     parser::visit(reader, 128, &mut visitor)?;
+///
+///     assert_eq!(visitor.0, 3);
+///
+///     Ok(())
+/// }
+/// ```
+pub fn visit<R: input_reader::ReadInput>(
+    reader: R,
+    max_depth: usize,
+    visitor: &mut dyn Visitor,
+) -> Result<()> {
+    let mut parser = Parser::new(reader, max_depth, LexerOptions::default())?;
+    parser.visitor = Some(visitor);
+
+    parser.parse_value()?;
+    parser.parse_eof()
+}
+
+/// Returns whether the input forms a single valid JSON document.
+///
+/// This is a shorthand for [`validate`] discarding the error.
+///
+/// # Examples
+///
+/// ```
+/// use json::{input_reader::MemoryReader, parser};
+///
+/// fn main() -> parser::Result<()> {
+///     let reader = MemoryReader::new(r#"{"a": 1}"#.as_bytes())?;
+///     assert!(parser::is_valid_json(reader, 128));
+///
+///     let reader = MemoryReader::new(r#"{"a" 1}"#.as_bytes())?;
+///     assert!(!parser::is_valid_json(reader, 128));
+///
+///     Ok(())
+/// }
+/// ```
+pub fn is_valid_json<R: input_reader::ReadInput>(reader: R, max_depth: usize) -> bool {
+    validate(reader, max_depth).is_ok()
+}
+
+/// A trait for receiving the contents of a JSON document from [`visit`].
+///
+/// Every method does nothing by default, so implementors only override the
+/// events they care about. Containers are reported as a start event, the
+/// events of their contents, then an end event, with every object value
+/// preceded by its key.
+///
+/// Numbers that fit an [`i64`] are reported through [`visit_i64`] and all
+/// others through [`visit_f64`].
+///
+/// [`visit_i64`]: Visitor::visit_i64
+/// [`visit_f64`]: Visitor::visit_f64
+pub trait Visitor {
+    /// Called for a `null` literal.
+    fn visit_null(&mut self) {}
+
+    /// Called for a `true` or `false` literal.
+    fn visit_bool(&mut self, _value: bool) {}
+
+    /// Called for a number written as an integer that fits an [`i64`], such
+    /// as `-12`. A fraction or an exponent, even `1.0` or `1e2`, makes the
+    /// number go to [`visit_f64`] instead.
+    ///
+    /// [`visit_f64`]: Visitor::visit_f64
+    fn visit_i64(&mut self, _value: i64) {}
+
+    /// Called for every number not reported through [`visit_i64`]. Numbers
+    /// beyond the range of an [`f64`] become infinite, and those too small
+    /// for it become zero.
+    ///
+    /// [`visit_i64`]: Visitor::visit_i64
+    fn visit_f64(&mut self, _value: f64) {}
+
+    /// Called for a string value, passed with its escape sequences resolved.
+    fn visit_str(&mut self, _value: &str) {}
+
+    /// Called for an object key before the events of its value, passed with
+    /// its escape sequences resolved like a string value.
+    fn visit_key(&mut self, _key: &str) {}
+
+    /// Called at the `{` opening an object.
+    fn visit_start_object(&mut self) {}
+
+    /// Called at the `}` closing an object, after the events of its last
+    /// value.
+    fn visit_end_object(&mut self) {}
+
+    /// Called at the `[` opening an array.
+    fn visit_start_array(&mut self) {}
+
+    /// Called at the `]` closing an array, after the events of its last
+    /// element.
+    fn visit_end_array(&mut self) {}
+}
+
+struct Parser<'v, R> {
+    lexer: Lexer<R>,
+    max_depth: usize,
+    stack: Vec<(Container, Pos)>,
+    visitor: Option<&'v mut dyn Visitor>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Container {
+    Object,
+    Array,
+}
+
+use Container::{Array, Object};
+
+impl<'v, R: input_reader::ReadInput> Parser<'v, R> {
+    fn new(reader: R, max_depth: usize, options: LexerOptions) -> Result<Self> {
+        let mut parser = Self {
+            lexer: Lexer::lazy_with_options(reader, options),
+            max_depth,
+            stack: Vec::new(),
+            visitor: None,
+        };
+        parser.bump()?;
+
+        Ok(parser)
+    }
+
+    fn pos(&self) -> Pos {
+        match self.lexer.peek() {
+            Some(token) => token.start(),
+            None => self.lexer.position(),
+        }
+    }
+
+    fn error(&self, repr: Repr) -> Error {
+        Error {
+            repr,
+            pos: Some(self.pos()),
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<TokenKind>> {
+        while self.lexer.peek().is_some_and(lexer::Token::is_whitespace) {
+            self.bump()?;
+        }
+
+        Ok(self.lexer.peek().map(lexer::Token::kind))
+    }
+
+    fn bump(&mut self) -> Result<()> {
+        self.lexer.consume().map_err(|err| {
+            // A token cut short by the end of input can't be recovered from,
+            // unlike one followed by more input.
+            let truncated =
+                matches!(err.kind(), lexer::ErrorKind::Expected(_)) && self.lexer.has_reached_eof();
+
+            Error::from(if truncated {
+                Repr::Truncated(err)
+            } else {
+                Repr::Lexer(err)
+            })
+        })
+    }
+
+    fn visit(&mut self, f: impl FnOnce(&mut dyn Visitor)) {
+        if let Some(visitor) = self.visitor.as_deref_mut() {
+            f(visitor);
+        }
+    }
+
+    fn visit_literal(&mut self) {
+        let (visitor, token) = match (self.visitor.as_deref_mut(), self.lexer.peek()) {
+            (Some(visitor), Some(token)) => (visitor, token),
+            _ => return,
+        };
+
+        let raw = token.raw();
+        match token.kind() {
+            TokenKind::Literal { kind: Null } => visitor.visit_null(),
+            TokenKind::Literal { kind: Bool } => visitor.visit_bool(raw == "true"),
+            TokenKind::Literal { kind: Num } => match raw.parse() {
+                Ok(value) => visitor.visit_i64(value),
+                Err(_) => visitor.visit_f64(raw.parse().unwrap_or(f64::NAN)),
+            },
+            TokenKind::Literal { kind: Str } => visitor.visit_str(&unescape(raw)),
+            _ => {}
+        }
+    }
+
+    fn enter(&mut self) -> Result<Pos> {
+        if self.stack.len() >= self.max_depth {
+            return Err(self.error(DepthLimitExceeded(self.max_depth)));
+        }
+
+        let opened_at = self.pos();
+        self.bump()?;
+
+        Ok(opened_at)
+    }
+
+    fn parse_value(&mut self) -> Result<()> {
+        loop {
+            match self.peek()? {
+                Some(TokenKind::Literal { .. }) => {
+                    self.visit_literal();
+                    self.bump()?;
+                }
+                Some(TokenKind::OpenBrace) => {
+                    let opened_at = self.enter()?;
+                    self.visit(|visitor| visitor.visit_start_object());
+
+                    match self.peek()? {
+                        Some(TokenKind::CloseBrace) => {
+                            self.bump()?;
+                            self.visit(|visitor| visitor.visit_end_object());
+                        }
+                        Some(TokenKind::CloseBracket) => {
+                            return Err(self.error(MismatchedClose(Object, opened_at)))
+                        }
+                        _ => {
+                            self.stack.push((Object, opened_at));
+                            self.parse_key()?;
+                            continue;
+                        }
+                    }
+                }
+                Some(TokenKind::OpenBracket) => {
+                    let opened_at = self.enter()?;
+                    self.visit(|visitor| visitor.visit_start_array());
+
+                    match self.peek()? {
+                        Some(TokenKind::CloseBracket) => {
+                            self.bump()?;
+                            self.visit(|visitor| visitor.visit_end_array());
+                        }
+                        Some(TokenKind::CloseBrace) => {
+                            return Err(self.error(MismatchedClose(Array, opened_at)))
+                        }
+                        _ => {
+                            self.stack.push((Array, opened_at));
+                            continue;
+                        }
+                    }
+                }
+                Some(_) => return Err(self.error(Expected(Value))),
+                // Only the top-level value is parsed with no container open.
+                None if self.stack.is_empty() => return Err(self.error(EmptyInput)),
+                None => return Err(self.error(UnexpectedEof)),
+            }
+
+            // A value has just ended, so close every container it completes
+            // and stop before the next value of the innermost open one.
+            loop {
+                match self.stack.last().copied() {
+                    Some((Object, opened_at)) => match self.peek()? {
+                        Some(TokenKind::Comma) => {
+                            self.bump()?;
+                            self.parse_key()?;
+                            break;
+                        }
+                        Some(TokenKind::CloseBrace) => {
+                            self.bump()?;
+                            self.stack.pop();
+                            self.visit(|visitor| visitor.visit_end_object());
+                        }
+                        Some(TokenKind::CloseBracket) => {
+                            return Err(self.error(MismatchedClose(Object, opened_at)))
+                        }
+                        Some(_) => return Err(self.error(Expected(CommaOrCloseBrace))),
+                        None => return Err(self.error(UnexpectedEof)),
+                    },
+                    Some((Array, opened_at)) => match self.peek()? {
+                        Some(TokenKind::Comma) => {
+                            self.bump()?;
+                            break;
+                        }
+                        Some(TokenKind::CloseBracket) => {
+                            self.bump()?;
+                            self.stack.pop();
+                            self.visit(|visitor| visitor.visit_end_array());
+                        }
+                        Some(TokenKind::CloseBrace) => {
+                            return Err(self.error(MismatchedClose(Array, opened_at)))
+                        }
+                        Some(_) => return Err(self.error(Expected(CommaOrCloseBracket))),
+                        None => return Err(self.error(UnexpectedEof)),
+                    },
+                    None => return Ok(()),
+                }
+            }
+        }
+    }
+
+    fn parse_key(&mut self) -> Result<()> {
+        match self.peek()? {
+            Some(TokenKind::Literal { kind: Str } | TokenKind::Identifier) => {
+                if let (Some(visitor), Some(token)) =
+                    (self.visitor.as_deref_mut(), self.lexer.peek())
+                {
+                    visitor.visit_key(&unescape(token.raw()));
+                }
+                self.bump()?;
+            }
+            Some(_) => return Err(self.error(Expected(Key))),
+            None => return Err(self.error(UnexpectedEof)),
+        }
+
+        match self.peek()? {
+            Some(TokenKind::Colon) => self.bump(),
+            Some(_) => Err(self.error(Expected(Colon))),
+            None => Err(self.error(UnexpectedEof)),
+        }
+    }
+
+    fn parse_eof(&mut self) -> Result<()> {
+        match self.peek()? {
+            Some(_) => Err(self.error(Expected(Eof))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Resolves the escape sequences of a string literal's contents as produced by
+/// the lexer. Unpaired surrogates become U+FFFD REPLACEMENT CHARACTER.
+fn unescape(raw: &str) -> String {
+    let mut unescaped = String::with_capacity(raw.len());
+    let mut units = Vec::new();
+    let mut chars = raw.chars();
+
+    while let Some(c) = chars.next() {
+        let escaped = if c == '\\' { chars.next() } else { None };
+        if escaped != Some('u') && !units.is_empty() {
+            let decoded = char::decode_utf16(units.drain(..));
+            unescaped.extend(decoded.map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)));
+        }
+
+        match escaped {
+            None => unescaped.push(c),
+            Some('b') => unescaped.push('\u{8}'),
+            Some('f') => unescaped.push('\u{c}'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some('u') => units.push(hex_value(&mut chars, 4) as u16),
+            Some('x') => unescaped.push(char::from(hex_value(&mut chars, 2) as u8)),
+            Some(c) => unescaped.push(c),
+        }
+    }
+
+    let decoded = char::decode_utf16(units);
+    unescaped.extend(decoded.map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)));
+
+    unescaped
+}
+
+fn hex_value(chars: &mut impl Iterator<Item = char>, count: usize) -> u32 {
+    chars
+        .take(count)
+        .filter_map(|c| c.to_digit(16))
+        .fold(0, |value, digit| value * 16 + digit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use input_reader::MemoryReader;
+
+    const MAX_DEPTH: usize = 128;
+
+    fn is_valid(source: &str) -> Result<bool> {
+        let reader = MemoryReader::new(source.as_bytes())?;
+
+        Ok(is_valid_json(reader, MAX_DEPTH))
+    }
+
+    #[test]
+    fn test_valid_scalars() -> Result<()> {
+        assert!(is_valid("null")?);
+        assert!(is_valid("true")?);
+        assert!(is_valid(" false ")?);
+        assert!(is_valid("-1.5e3")?);
+        assert!(is_valid(r#""json""#)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_valid_containers() -> Result<()> {
+        assert!(is_valid("[]")?);
+        assert!(is_valid("{}")?);
+        assert!(is_valid("[1, [2, [3]], {}]")?);
+        assert!(is_valid(r#"{"a": {"b": [null, true]}, "c": "d"}"#)?);
+        assert!(is_valid(include_str!("../example.json"))?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_structure() -> Result<()> {
+        assert!(!is_valid("")?);
+        assert!(!is_valid("   ")?);
+        assert!(!is_valid("[1, 2")?);
+        assert!(!is_valid("[1, 2}")?);
+        assert!(!is_valid("[1 2]")?);
+        assert!(!is_valid("[1, ]")?);
+        assert!(!is_valid("[, 1]")?);
+        assert!(!is_valid("]")?);
+        assert!(!is_valid(r#"{"a" 1}"#)?);
+        assert!(!is_valid(r#"{"a": }"#)?);
+        assert!(!is_valid(r#"{"a": 1,}"#)?);
+        assert!(!is_valid(r#"{1: 2}"#)?);
+        assert!(!is_valid(r#"{"a": 1"#)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_identifier_keys() -> Result<()> {
+        let validate_json5 = |source: &str| -> Result<()> {
+            let options = LexerOptions {
+                json5: true,
+                ..LexerOptions::default()
+            };
+            let reader = MemoryReader::new(source.as_bytes())?;
+
+            validate_with(reader, MAX_DEPTH, options)
+        };
+
+        validate_json5("{a:1}")?;
+        validate_json5("{$b_2: true}")?;
+        validate_json5(r#"{a: {"b": [1, 2]}, c_: null}"#)?;
+
+        let err = validate_json5("{a}").unwrap_err();
+        assert!(matches!(err.repr, Expected(Colon)));
+        let err = validate_json5("[a]").unwrap_err();
+        assert!(matches!(err.repr, Expected(Value)));
+        assert!(!is_valid("{a:1}")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_multiple_values() -> Result<()> {
+        assert!(!is_valid("1 2")?);
+        assert!(!is_valid("{} []")?);
+        assert!(!is_valid("null,")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_tokens() -> Result<()> {
+        assert!(!is_valid("[nul]")?);
+        assert!(!is_valid(r#"{"a": "b}"#)?);
+        assert!(!is_valid("[1] x")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_input() -> Result<()> {
+        for source in ["", " \t\r\n", "\u{feff}", "\u{feff}  "] {
+            let reader = MemoryReader::new(source.as_bytes())?;
+            let err = validate(reader, MAX_DEPTH).unwrap_err();
+            assert!(matches!(err.repr, EmptyInput));
+            assert!(err.is_empty_input());
+            assert!(!err.recoverable());
+
+            let reader = MemoryReader::new(source.as_bytes())?;
+            assert!(!validate_optional(reader, MAX_DEPTH)?);
+        }
+
+        let reader = MemoryReader::new("\u{feff} [true] ".as_bytes())?;
+        assert!(validate_optional(reader, MAX_DEPTH)?);
+
+        let reader = MemoryReader::new("[".as_bytes())?;
+        assert!(matches!(
+            validate(reader, MAX_DEPTH).unwrap_err().repr,
+            UnexpectedEof
+        ));
+        let reader = MemoryReader::new("  [1] 2".as_bytes())?;
+        assert!(validate_optional(reader, MAX_DEPTH).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_error() -> Result<()> {
+        let source = "[1, 2 3]";
+        let reader = MemoryReader::new(source.as_bytes())?;
+        let err = validate(reader, MAX_DEPTH).unwrap_err();
+
+        assert_eq!(
+            err.render(source),
+            "error: expected ',' or ']'\n --> 1:7\n  |\n1 | [1, 2 3]\n  |       ^"
+        );
+
+        let source = "[\n\n\n\n\n\n\n\n\n";
+        let reader = MemoryReader::new(source.as_bytes())?;
+        let err = validate(reader, MAX_DEPTH).unwrap_err();
+
+        assert_eq!(
+            err.render(source),
+            "error: unexpected end of input\n  --> 10:1\n   |\n10 | \n   | ^"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mismatched_close() -> Result<()> {
+        let reader = MemoryReader::new("[1, 2}".as_bytes())?;
+        let err = validate(reader, MAX_DEPTH).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected ']' to match '[' at 1:1, found '}' at 1:6"
+        );
+        assert!(err.recoverable());
+
+        let reader = MemoryReader::new("[}".as_bytes())?;
+        let err = validate(reader, MAX_DEPTH).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected ']' to match '[' at 1:1, found '}' at 1:2"
+        );
+        let reader = MemoryReader::new("[[ }]".as_bytes())?;
+        let err = validate(reader, MAX_DEPTH).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected ']' to match '[' at 1:2, found '}' at 1:4"
+        );
+        let reader = MemoryReader::new("{]".as_bytes())?;
+        let err = validate(reader, MAX_DEPTH).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected '}' to match '{' at 1:1, found ']' at 1:2"
+        );
+
+        let reader = MemoryReader::new("{\"a\": [],\n  \"b\": {\"c\": 1]}".as_bytes())?;
+        let err = validate(reader, MAX_DEPTH).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected '}' to match '{' at 2:8, found ']' at 2:15"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_depth() -> Result<()> {
+        let reader = MemoryReader::new("1".as_bytes())?;
+        validate(reader, 0)?;
+
+        let reader = MemoryReader::new("[[{}]]".as_bytes())?;
+        validate(reader, 3)?;
+
+        let reader = MemoryReader::new("[[{}]]".as_bytes())?;
+        assert!(validate(reader, 2).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deep_nesting() -> Result<()> {
+        const DEPTH: usize = 1_000_000;
+
+        let source = "[".repeat(DEPTH) + &"]".repeat(DEPTH);
+
+        let reader = MemoryReader::new(source.as_bytes())?;
+        validate(reader, DEPTH)?;
+
+        let reader = MemoryReader::new(source.as_bytes())?;
+        let err = validate(reader, DEPTH - 1).unwrap_err();
+        assert!(matches!(err.repr, DepthLimitExceeded(_)));
+
+        Ok(())
+    }
+
+    #[derive(Default)]
+    struct Sum(f64);
+
+    impl Visitor for Sum {
+        fn visit_i64(&mut self, value: i64) {
+            self.0 += value as f64;
+        }
+
+        fn visit_f64(&mut self, value: f64) {
+            self.0 += value;
+        }
+    }
+
+    #[derive(Default)]
+    struct Events(Vec<String>);
+
+    impl Visitor for Events {
+        fn visit_null(&mut self) {
+            self.0.push(String::from("null"));
+        }
+
+        fn visit_bool(&mut self, value: bool) {
+            self.0.push(value.to_string());
+        }
+
+        fn visit_i64(&mut self, value: i64) {
+            self.0.push(format!("i64 {}", value));
+        }
+
+        fn visit_f64(&mut self, value: f64) {
+            self.0.push(format!("f64 {}", value));
+        }
+
+        fn visit_str(&mut self, value: &str) {
+            self.0.push(format!("str {}", value));
+        }
+
+        fn visit_key(&mut self, key: &str) {
+            self.0.push(format!("key {}", key));
+        }
+
+        fn visit_start_object(&mut self) {
+            self.0.push(String::from("{"));
+        }
+
+        fn visit_end_object(&mut self) {
+            self.0.push(String::from("}"));
+        }
+
+        fn visit_start_array(&mut self) {
+            self.0.push(String::from("["));
+        }
+
+        fn visit_end_array(&mut self) {
+            self.0.push(String::from("]"));
+        }
+    }
+
+    #[test]
+    fn test_visit_sum() -> Result<()> {
+        let reader =
+            MemoryReader::new(r#"{"a": [1, 2.5, {"b": -3}], "c": "4", "d": 1e2}"#.as_bytes())?;
+        let mut sum = Sum::default();
+        visit(reader, MAX_DEPTH, &mut sum)?;
+
+        assert_eq!(sum.0, 100.5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_visit_extreme_exponents() -> Result<()> {
+        let cases = [
+            ("1e400", f64::INFINITY),
+            ("-1e400", f64::NEG_INFINITY),
+            ("1e-400", 0.0),
+            ("1E99999999", f64::INFINITY),
+        ];
+
+        for (source, expected) in cases {
+            let mut sum = Sum::default();
+            visit(MemoryReader::new(source.as_bytes())?, MAX_DEPTH, &mut sum)?;
+            assert_eq!(sum.0, expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_visit_events() -> Result<()> {
+        let source = r#"{"k\"1": [null, true, {}, []], "k2": "a\nbé\ud83d\ude00\u0041"}"#;
+        let reader = MemoryReader::new(source.as_bytes())?;
+        let mut events = Events::default();
+        visit(reader, MAX_DEPTH, &mut events)?;
+
+        assert_eq!(
+            events.0,
+            [
+                "{",
+                "key k\"1",
+                "[",
+                "null",
+                "true",
+                "{",
+                "}",
+                "[",
+                "]",
+                "]",
+                "key k2",
+                "str a\nbé😀A",
+                "}",
+            ]
+        );
+
+        let reader = MemoryReader::new("[1, 2".as_bytes())?;
+        let mut events = Events::default();
+        assert!(visit(reader, MAX_DEPTH, &mut events).is_err());
+        assert_eq!(events.0, ["[", "i64 1", "i64 2"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clone_error() -> Result<()> {
+        let source = r#"{"a" 1}"#;
+        let reader = MemoryReader::new(source.as_bytes())?;
+        let err = validate(reader, MAX_DEPTH).unwrap_err();
+        let cloned = err.clone();
+
+        assert_eq!(cloned.to_string(), "expected ':'");
+        assert_eq!(cloned.render(source), err.render(source));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recoverable() -> Result<()> {
+        let reader = MemoryReader::new(r#"{"a" 1}"#.as_bytes())?;
+        assert!(validate(reader, MAX_DEPTH).unwrap_err().recoverable());
+        let reader = MemoryReader::new("[nul]".as_bytes())?;
+        assert!(validate(reader, MAX_DEPTH).unwrap_err().recoverable());
+        let reader = MemoryReader::new("[1 e5]".as_bytes())?;
+        assert!(validate(reader, MAX_DEPTH).unwrap_err().recoverable());
+
+        let reader = MemoryReader::new("[[1]]".as_bytes())?;
+        assert!(!validate(reader, 1).unwrap_err().recoverable());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncated_token_is_fatal() -> Result<()> {
+        for source in [r#"["abc"#, "[tr", "[-", r#""abc"#, "nu", r#"{"a": "b"#] {
+            let reader = MemoryReader::new(source.as_bytes())?;
+            let err = validate(reader, MAX_DEPTH).unwrap_err();
+            assert!(matches!(err.repr, Repr::Truncated(_)), "{}", source);
+            assert!(!err.recoverable(), "{}", source);
+        }
+
+        let reader = MemoryReader::new("[tr]".as_bytes())?;
+        assert!(validate(reader, MAX_DEPTH).unwrap_err().recoverable());
+        let reader = MemoryReader::new("[\"abc\n\"]".as_bytes())?;
+        assert!(validate(reader, MAX_DEPTH).unwrap_err().recoverable());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_io_error_is_fatal() {
+        use std::io;
+
+        struct Failing;
+
+        impl io::Read for Failing {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "access denied",
+                ))
+            }
+        }
+
+        let err = Error::from(MemoryReader::new(Failing).unwrap_err());
+        assert!(!err.recoverable());
+    }
+}